@@ -1,58 +1,514 @@
 //! OpenSky Network API client.
 
-use reqwest::Client;
+use futures_util::stream::{self, Stream, StreamExt};
+use governor::clock::Clock;
+use governor::middleware::NoOpMiddleware;
+use governor::state::{InMemoryState, NotKeyed};
+use governor::RateLimiter;
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, instrument, warn};
+use web_time::Instant;
 
 use crate::error::{IfoError, Result};
-use crate::models::{Aircraft, BoundingBox, OpenSkyResponse};
+use crate::limits::FLIGHTS_BY_AIRCRAFT_MAX_INTERVAL_SECS;
+use crate::models::{
+    self, Aircraft, AreaSnapshot, BoundingBox, Coordinate, FlightInfo, OpenSkyResponse,
+};
+use crate::source::{AircraftSource, MaybeSend};
+
+const DEFAULT_BASE_URL: &str = "https://opensky-network.org/api";
+const DEFAULT_USER_AGENT: &str = "IFO-CLI/2.0 (Rust)";
+pub(crate) const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_MAX_RESPONSE_BYTES: usize = 50 * 1024 * 1024;
+const DEFAULT_RATE_LIMIT_RETRIES: u32 = 1;
+const DEFAULT_MAX_RETRY_WAIT: Duration = Duration::from_secs(30);
+const DEFAULT_TILED_RETRY_BUDGET: u32 = 3;
+const DEFAULT_CACHE_GRID_DEG: f64 = 0.01;
+
+/// How a client handles HTTP redirects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedirectPolicy {
+    /// Follow redirects, up to reqwest's default limit of 10.
+    #[default]
+    Follow,
+    /// Never follow redirects; a redirect response is reported as an error.
+    None,
+    /// Follow at most this many redirects.
+    Limited(usize),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RedirectPolicy {
+    fn to_reqwest(self) -> reqwest::redirect::Policy {
+        use reqwest::redirect::Policy;
+
+        match self {
+            RedirectPolicy::Follow => Policy::default(),
+            RedirectPolicy::None => Policy::none(),
+            RedirectPolicy::Limited(max) => Policy::limited(max),
+        }
+    }
+}
+
+/// Transport settings shared by the OpenSky and Nominatim client builders.
+#[derive(Debug, Clone)]
+pub(crate) struct HttpOptions {
+    pub(crate) user_agent: String,
+    pub(crate) timeout: Duration,
+    pub(crate) danger_accept_invalid_certs: bool,
+    pub(crate) redirect_policy: RedirectPolicy,
+    pub(crate) max_response_bytes: usize,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) root_certificates: Vec<reqwest::Certificate>,
+}
+
+impl HttpOptions {
+    pub(crate) fn new(user_agent: &str) -> Self {
+        Self {
+            user_agent: user_agent.to_string(),
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            danger_accept_invalid_certs: false,
+            redirect_policy: RedirectPolicy::default(),
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            #[cfg(not(target_arch = "wasm32"))]
+            root_certificates: Vec::new(),
+        }
+    }
+
+    /// Build the underlying reqwest client.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn build_client(&self) -> Result<Client> {
+        let mut builder = Client::builder()
+            .user_agent(&self.user_agent)
+            .timeout(self.timeout)
+            .danger_accept_invalid_certs(self.danger_accept_invalid_certs)
+            .redirect(self.redirect_policy.to_reqwest())
+            // Needs reqwest's `gzip` feature; large area queries compress well.
+            .gzip(true);
+        for cert in &self.root_certificates {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        builder.build().map_err(IfoError::NetworkError)
+    }
+
+    /// Build the underlying reqwest client.
+    ///
+    /// On wasm32 reqwest goes through the browser's fetch API, which offers
+    /// no timeout, certificate or redirect settings, so only the user agent
+    /// is applied. The browser negotiates compression itself.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn build_client(&self) -> Result<Client> {
+        Client::builder()
+            .user_agent(&self.user_agent)
+            .build()
+            .map_err(IfoError::NetworkError)
+    }
+}
+
+/// Read a response body, failing with [`IfoError::ResponseTooLarge`] as soon
+/// as it exceeds `limit` bytes rather than buffering all of it.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn read_body(mut response: Response, limit: usize) -> Result<Vec<u8>> {
+    if response
+        .content_length()
+        .is_some_and(|len| len > limit as u64)
+    {
+        return Err(IfoError::ResponseTooLarge { limit });
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > limit {
+            return Err(IfoError::ResponseTooLarge { limit });
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Read a response body, failing with [`IfoError::ResponseTooLarge`] if it
+/// exceeds `limit` bytes.
+///
+/// The browser's fetch API hands over the body in one piece, so only a
+/// declared `Content-Length` can stop the download early.
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn read_body(response: Response, limit: usize) -> Result<Vec<u8>> {
+    if response
+        .content_length()
+        .is_some_and(|len| len > limit as u64)
+    {
+        return Err(IfoError::ResponseTooLarge { limit });
+    }
+    let body = response.bytes().await?;
+    if body.len() > limit {
+        return Err(IfoError::ResponseTooLarge { limit });
+    }
+    Ok(body.to_vec())
+}
+
+/// Wait for `duration` on Tokio's timer.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Wait for `duration` on the browser's `setTimeout`; there is no Tokio
+/// runtime to drive Tokio's timer.
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    futures_timer::Delay::new(duration).await;
+}
+
+/// The clock rate limiters run on: governor's default natively, and
+/// `WebClock` on wasm32.
+#[cfg(not(target_arch = "wasm32"))]
+pub use governor::clock::DefaultClock;
+
+/// The clock rate limiters run on: governor's default natively, and
+/// [`WebClock`] on wasm32.
+#[cfg(target_arch = "wasm32")]
+pub type DefaultClock = WebClock;
+
+/// A rate limiter for [`OpenSkyClientBuilder::rate_limiter`], on
+/// [`DefaultClock`].
+///
+/// Natively this is governor's `DefaultDirectRateLimiter`, so
+/// `RateLimiter::direct(quota)` builds one. On wasm32 build it with
+/// `RateLimiter::direct_with_clock(quota, &WebClock::default())`.
+pub type DefaultDirectRateLimiter = RateLimiter<
+    NotKeyed,
+    InMemoryState,
+    DefaultClock,
+    NoOpMiddleware<<DefaultClock as Clock>::Instant>,
+>;
+
+/// A shared rate limiter as [`OpenSkyClient`] uses it, whatever its clock.
+trait Pacer: fmt::Debug + Send + Sync {
+    /// Take a cell, or say how long until the next one is free.
+    fn try_acquire(&self) -> std::result::Result<(), Duration>;
+}
+
+/// A limiter with the clock it was built on: governor reports the next free
+/// cell as an instant on that clock, and only the clock can turn it into a
+/// wait.
+#[derive(Debug)]
+struct ClockedLimiter<C: Clock> {
+    limiter: Arc<RateLimiter<NotKeyed, InMemoryState, C, NoOpMiddleware<C::Instant>>>,
+    clock: C,
+}
+
+impl<C> Pacer for ClockedLimiter<C>
+where
+    C: Clock + fmt::Debug + Send + Sync,
+    C::Instant: Send + Sync,
+{
+    fn try_acquire(&self) -> std::result::Result<(), Duration> {
+        self.limiter
+            .check()
+            .map_err(|not_until| not_until.wait_time_from(self.clock.now()))
+    }
+}
+
+/// A governor clock for wasm32.
+///
+/// governor's default clock reads the time through `std`, which panics in
+/// the browser. This one counts with [`web_time::Instant`], i.e.
+/// `performance.now()`, from the first time any `WebClock` is read, so
+/// separately created clocks agree.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WebClock {
+    _private: (),
+}
+
+#[cfg(target_arch = "wasm32")]
+impl governor::clock::Clock for WebClock {
+    type Instant = governor::nanos::Nanos;
+
+    fn now(&self) -> Self::Instant {
+        static ORIGIN: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+        ORIGIN.get_or_init(Instant::now).elapsed().into()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl governor::clock::ReasonablyRealtime for WebClock {}
+
+/// Read and parse a JSON response body of at most `limit` bytes.
+pub(crate) async fn read_json<T: DeserializeOwned>(response: Response, limit: usize) -> Result<T> {
+    Ok(serde_json::from_slice(&read_body(response, limit).await?)?)
+}
+
+/// Fail with [`IfoError::UnexpectedResponse`] if a response declares a
+/// non-JSON `Content-Type`, such as an HTML maintenance page served with a
+/// 200. A missing header is let through to the JSON parser.
+pub(crate) fn ensure_json(response: &Response, service: &str) -> Result<()> {
+    let declared = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    match declared {
+        Some(content_type) if !content_type.to_ascii_lowercase().contains("json") => {
+            Err(IfoError::UnexpectedResponse {
+                service: service.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Read an error response body as text for [`IfoError::ApiError`], or an
+/// empty string if it is unreadable or too large.
+pub(crate) async fn read_error_text(response: Response, limit: usize) -> String {
+    read_body(response, limit)
+        .await
+        .map(|body| String::from_utf8_lossy(&body).into_owned())
+        .unwrap_or_default()
+}
+
+/// Query parameter names used to send a bounding box.
+///
+/// Defaults to OpenSky's `lamin`/`lomin`/`lamax`/`lomax`; override them to
+/// talk to compatible mirrors that spell these differently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BboxParamNames {
+    pub lat_min: String,
+    pub lon_min: String,
+    pub lat_max: String,
+    pub lon_max: String,
+}
+
+impl Default for BboxParamNames {
+    fn default() -> Self {
+        Self {
+            lat_min: "lamin".to_string(),
+            lon_min: "lomin".to_string(),
+            lat_max: "lamax".to_string(),
+            lon_max: "lomax".to_string(),
+        }
+    }
+}
 
 /// Client for interacting with the OpenSky Network REST API.
+///
+/// Clones are cheap and share the HTTP connection pool and any rate
+/// limiter, so one client can be cloned into each request handler.
+#[derive(Clone)]
 pub struct OpenSkyClient {
     client: Client,
     base_url: String,
+    bbox_params: BboxParamNames,
+    max_response_bytes: usize,
+    rate_limiter: Option<Arc<dyn Pacer>>,
+    rate_limit_retries: u32,
+    max_retry_wait: Duration,
+    tiled_retry_budget: u32,
+    cache: Option<Arc<SnapshotCache>>,
+}
+
+/// Recent snapshots keyed by their bounding box snapped to a grid; see
+/// [`OpenSkyClientBuilder::cache_ttl`].
+struct SnapshotCache {
+    ttl: Duration,
+    grid_deg: f64,
+    entries: Mutex<HashMap<[i64; 4], (Instant, AreaSnapshot)>>,
+}
+
+impl SnapshotCache {
+    fn key(&self, bbox: BoundingBox) -> [i64; 4] {
+        [bbox.lat_min, bbox.lon_min, bbox.lat_max, bbox.lon_max]
+            .map(|deg| (deg / self.grid_deg).round() as i64)
+    }
+
+    fn get(&self, bbox: BoundingBox) -> Option<AreaSnapshot> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let (stored, snapshot) = entries.get(&self.key(bbox))?;
+        (stored.elapsed() < self.ttl).then(|| snapshot.clone())
+    }
+
+    fn insert(&self, bbox: BoundingBox, snapshot: &AreaSnapshot) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, (stored, _)| stored.elapsed() < self.ttl);
+        entries.insert(self.key(bbox), (Instant::now(), snapshot.clone()));
+    }
 }
 
 impl OpenSkyClient {
     /// Create a new OpenSky API client.
     pub fn new(timeout_secs: u64) -> Result<Self> {
-        let client = Client::builder()
-            .user_agent("IFO-CLI/2.0 (Rust)")
+        Self::builder()
             .timeout(Duration::from_secs(timeout_secs))
             .build()
-            .map_err(IfoError::NetworkError)?;
+    }
 
-        Ok(Self {
-            client,
-            base_url: "https://opensky-network.org/api".to_string(),
-        })
+    /// Start configuring a client with non-default options.
+    pub fn builder() -> OpenSkyClientBuilder {
+        OpenSkyClientBuilder::default()
     }
 
     /// Query aircraft within a geographic bounding box.
+    ///
+    /// Dropping the returned future before it completes aborts the request.
     pub async fn get_aircraft_in_area(&self, bbox: BoundingBox) -> Result<Vec<Aircraft>> {
-        let url = format!("{}/states/all", self.base_url);
+        Ok(self.get_snapshot_in_area(bbox).await?.aircraft)
+    }
+
+    /// Like [`get_aircraft_in_area`](Self::get_aircraft_in_area), but gives up
+    /// as soon as `token` is cancelled, returning [`IfoError::Cancelled`].
+    ///
+    /// The in-flight request is dropped, and with it the connection.
+    pub async fn get_aircraft_in_area_cancellable(
+        &self,
+        bbox: BoundingBox,
+        token: CancellationToken,
+    ) -> Result<Vec<Aircraft>> {
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(IfoError::Cancelled),
+            result = self.get_aircraft_in_area(bbox) => result,
+        }
+    }
+
+    /// Query a large area one tile at a time, yielding each tile's aircraft
+    /// as it arrives instead of collecting them all.
+    ///
+    /// The area is split by [`BoundingBox::tiles`], and the next request is
+    /// only sent once the stream is polled again. Aircraft on a shared tile
+    /// edge can appear twice; pass the collected tiles through
+    /// [`models::dedup_freshest`] for one sorted report per aircraft. An
+    /// invalid `max_span` is yielded as the only item.
+    pub fn stream_area_tiled(
+        &self,
+        bbox: BoundingBox,
+        max_span: f64,
+    ) -> impl Stream<Item = Result<Vec<Aircraft>>> + '_ {
+        let tiles: Vec<Result<BoundingBox>> = match bbox.tiles(max_span) {
+            Ok(tiles) => tiles.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        let budget = Arc::new(AtomicU32::new(self.tiled_retry_budget));
+        stream::iter(tiles).then(move |tile| {
+            let budget = Arc::clone(&budget);
+            async move { Ok(self.fetch_snapshot(tile?, Some(&budget)).await?.aircraft) }
+        })
+    }
 
+    /// Query a large area one tile at a time and collect what could be
+    /// fetched, instead of failing on the first bad tile.
+    ///
+    /// The area is split by [`BoundingBox::tiles`] and the tiles are
+    /// requested one after another. Aircraft from the tiles that answered
+    /// are merged by [`models::dedup_freshest`]; each tile that failed is
+    /// listed with its error, so callers can tell regions with no traffic
+    /// from regions with no data. Only an invalid `max_span` fails the call.
+    pub async fn get_aircraft_in_area_tiled(
+        &self,
+        bbox: BoundingBox,
+        max_span: f64,
+    ) -> Result<TiledResult> {
+        self.get_aircraft_in_area_tiled_with_progress(bbox, max_span, |_, _| {})
+            .await
+    }
+
+    /// Like [`get_aircraft_in_area_tiled`](Self::get_aircraft_in_area_tiled),
+    /// calling `on_progress(completed, total)` after each tile, whether it
+    /// succeeded or failed, e.g. to drive a progress bar.
+    pub async fn get_aircraft_in_area_tiled_with_progress(
+        &self,
+        bbox: BoundingBox,
+        max_span: f64,
+        on_progress: impl Fn(usize, usize),
+    ) -> Result<TiledResult> {
+        let tiles = bbox.tiles(max_span)?;
+        let total = tiles.len();
+        let budget = AtomicU32::new(self.tiled_retry_budget);
+        let mut reports = Vec::new();
+        let mut failed = Vec::new();
+        for (i, tile) in tiles.into_iter().enumerate() {
+            match self.fetch_snapshot(tile, Some(&budget)).await {
+                Ok(snapshot) => reports.extend(snapshot.aircraft),
+                Err(e) => {
+                    warn!(tile = ?tile, error = %e, "tile failed");
+                    failed.push((tile, e));
+                }
+            }
+            on_progress(i + 1, total);
+        }
+        Ok(TiledResult {
+            aircraft: models::dedup_freshest(reports),
+            failed,
+        })
+    }
+
+    /// Query aircraft inside an arbitrary polygon.
+    ///
+    /// Fetches the polygon's bounding box and keeps aircraft whose position
+    /// passes [`models::point_in_polygon`]; aircraft without a position are
+    /// dropped. The same antimeridian caveat applies.
+    pub async fn get_aircraft_in_polygon(&self, polygon: &[Coordinate]) -> Result<Vec<Aircraft>> {
+        let bbox = BoundingBox::enclosing(polygon)?;
+        let mut aircraft = self.get_aircraft_in_area(bbox).await?;
+        aircraft.retain(|ac| {
+            ac.position()
+                .is_some_and(|pos| models::point_in_polygon(pos, polygon))
+        });
+        Ok(aircraft)
+    }
+
+    /// Query aircraft within a bounding box, keeping the snapshot time.
+    ///
+    /// With [`cache_ttl`](OpenSkyClientBuilder::cache_ttl) set, a recent
+    /// snapshot of the same box is returned without a request.
+    ///
+    /// Nothing is held across await points besides the request itself, so
+    /// dropping the returned future before it completes aborts the request.
+    pub async fn get_snapshot_in_area(&self, bbox: BoundingBox) -> Result<AreaSnapshot> {
+        self.fetch_snapshot(bbox, None).await
+    }
+
+    /// [`get_snapshot_in_area`](Self::get_snapshot_in_area), drawing 429
+    /// retries from `retry_budget` as well when one is shared by a tiled
+    /// query.
+    #[instrument(name = "opensky_states", skip_all, fields(bbox = ?bbox))]
+    async fn fetch_snapshot(
+        &self,
+        bbox: BoundingBox,
+        retry_budget: Option<&AtomicU32>,
+    ) -> Result<AreaSnapshot> {
+        if let Some(snapshot) = self.cache.as_ref().and_then(|cache| cache.get(bbox)) {
+            debug!(count = snapshot.aircraft.len(), "served from cache");
+            return Ok(snapshot);
+        }
+
+        debug!("query started");
+        let started = Instant::now();
         let response = self
-            .client
-            .get(&url)
-            .query(&[
-                ("lamin", bbox.lat_min.to_string()),
-                ("lomin", bbox.lon_min.to_string()),
-                ("lamax", bbox.lat_max.to_string()),
-                ("lomax", bbox.lon_max.to_string()),
-            ])
-            .send()
+            .send(|| self.states_request(bbox), started, retry_budget)
             .await?;
 
         if !response.status().is_success() {
             let status = response.status();
-            let text = response.text().await.unwrap_or_default();
+            warn!(
+                status = status.as_u16(),
+                elapsed_ms = elapsed_ms(started),
+                "request failed"
+            );
+            let text = read_error_text(response, self.max_response_bytes).await;
             return Err(IfoError::ApiError {
                 status: status.as_u16(),
                 message: text,
             });
         }
 
-        let data: OpenSkyResponse = response.json().await?;
+        ensure_json(&response, "OpenSky")?;
+        let data: OpenSkyResponse = read_json(response, self.max_response_bytes).await?;
 
         // Parse state vectors into aircraft
         let aircraft = match data.states {
@@ -63,7 +519,7 @@ impl OpenSkyClient {
                         Ok(ac) => result.push(ac),
                         Err(e) => {
                             // Log but don't fail on individual parsing errors
-                            eprintln!("Warning: Failed to parse state vector: {}", e);
+                            warn!(error = %e, "failed to parse state vector");
                         }
                     }
                 }
@@ -72,14 +528,1160 @@ impl OpenSkyClient {
             None => Vec::new(),
         };
 
-        Ok(aircraft)
+        info!(
+            count = aircraft.len(),
+            elapsed_ms = elapsed_ms(started),
+            "response received"
+        );
+
+        let snapshot = AreaSnapshot {
+            time: data.time,
+            aircraft,
+        };
+        if let Some(cache) = &self.cache {
+            cache.insert(bbox, &snapshot);
+        }
+        Ok(snapshot)
+    }
+
+    /// Flights of one aircraft between Unix timestamps `begin` and `end`.
+    ///
+    /// OpenSky only answers windows of up to
+    /// [`FLIGHTS_BY_AIRCRAFT_MAX_INTERVAL_SECS`]; longer or inverted windows
+    /// fail with [`IfoError::InvalidTimeWindow`] and malformed addresses with
+    /// [`IfoError::InvalidIcao24`], before any request is sent.
+    /// Flight data is batch-processed overnight, so recent flights may be
+    /// missing.
+    #[instrument(name = "opensky_flights_aircraft", skip(self))]
+    pub async fn get_flights_by_aircraft(
+        &self,
+        icao24: &str,
+        begin: i64,
+        end: i64,
+    ) -> Result<Vec<FlightInfo>> {
+        let icao24 = models::normalize_icao24(icao24)?;
+        if end <= begin {
+            return Err(IfoError::InvalidTimeWindow(
+                "end must be after begin".to_string(),
+            ));
+        }
+        if end - begin > FLIGHTS_BY_AIRCRAFT_MAX_INTERVAL_SECS as i64 {
+            return Err(IfoError::InvalidTimeWindow(format!(
+                "at most {} seconds per request",
+                FLIGHTS_BY_AIRCRAFT_MAX_INTERVAL_SECS
+            )));
+        }
+
+        debug!("query started");
+        let started = Instant::now();
+        let url = format!("{}/flights/aircraft", self.base_url);
+        let request = || {
+            self.client.get(&url).query(&[
+                ("icao24", icao24.clone()),
+                ("begin", begin.to_string()),
+                ("end", end.to_string()),
+            ])
+        };
+        let response = self.send(request, started, None).await?;
+
+        // OpenSky answers 404 when there are no flights in the window.
+        if response.status() == StatusCode::NOT_FOUND {
+            info!(
+                count = 0,
+                elapsed_ms = elapsed_ms(started),
+                "response received"
+            );
+            return Ok(Vec::new());
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            warn!(
+                status = status.as_u16(),
+                elapsed_ms = elapsed_ms(started),
+                "request failed"
+            );
+            let text = read_error_text(response, self.max_response_bytes).await;
+            return Err(IfoError::ApiError {
+                status: status.as_u16(),
+                message: text,
+            });
+        }
+
+        ensure_json(&response, "OpenSky")?;
+        let mut flights: Vec<FlightInfo> = read_json(response, self.max_response_bytes).await?;
+        for flight in &mut flights {
+            flight.callsign = flight
+                .callsign
+                .take()
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty());
+        }
+        info!(
+            count = flights.len(),
+            elapsed_ms = elapsed_ms(started),
+            "response received"
+        );
+        Ok(flights)
+    }
+
+    /// Send a request, pacing it through the rate limiter and retrying when
+    /// OpenSky answers 429 with a retry delay.
+    ///
+    /// The delay is slept before going back to the limiter, never while
+    /// holding one of its cells, so every attempt spends exactly one cell of
+    /// the shared budget and the server's delay and the limiter's pacing
+    /// overlap instead of adding up. A 429 without a delay, with one longer
+    /// than `max_retry_wait`, or after the last retry fails with
+    /// [`IfoError::RateLimitExceeded`], as does one arriving after a shared
+    /// `retry_budget` is used up.
+    async fn send(
+        &self,
+        request: impl Fn() -> RequestBuilder,
+        started: Instant,
+        retry_budget: Option<&AtomicU32>,
+    ) -> Result<Response> {
+        let mut retries = 0;
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                while let Err(wait) = limiter.try_acquire() {
+                    sleep(wait).await;
+                }
+            }
+
+            let response = request().send().await.inspect_err(|e| {
+                warn!(error = %e, elapsed_ms = elapsed_ms(started), "request failed");
+            })?;
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
+
+            let wait = retry_after(&response);
+            warn!(
+                retry_after_secs = wait.map(|w| w.as_secs()),
+                elapsed_ms = elapsed_ms(started),
+                "rate limited"
+            );
+            match wait {
+                Some(wait)
+                    if retries < self.rate_limit_retries
+                        && wait <= self.max_retry_wait
+                        && take_retry(retry_budget) =>
+                {
+                    retries += 1;
+                    sleep(wait).await;
+                }
+                _ => return Err(IfoError::RateLimitExceeded),
+            }
+        }
+    }
+
+    /// The full URL [`get_snapshot_in_area`](Self::get_snapshot_in_area)
+    /// would request for `bbox`, without sending anything.
+    pub fn states_url(&self, bbox: BoundingBox) -> Result<Url> {
+        Ok(self.states_request(bbox).build()?.url().clone())
+    }
+
+    fn states_request(&self, bbox: BoundingBox) -> RequestBuilder {
+        let url = format!("{}/states/all", self.base_url);
+        self.client.get(&url).query(&[
+            (&self.bbox_params.lat_min, bbox.lat_min.to_string()),
+            (&self.bbox_params.lon_min, bbox.lon_min.to_string()),
+            (&self.bbox_params.lat_max, bbox.lat_max.to_string()),
+            (&self.bbox_params.lon_max, bbox.lon_max.to_string()),
+        ])
+    }
+}
+
+impl AircraftSource for OpenSkyClient {
+    fn snapshot_in_area(
+        &self,
+        bbox: BoundingBox,
+    ) -> impl Future<Output = Result<AreaSnapshot>> + MaybeSend {
+        self.get_snapshot_in_area(bbox)
+    }
+}
+
+/// What [`OpenSkyClient::get_aircraft_in_area_tiled`] fetched.
+#[derive(Debug, Default)]
+pub struct TiledResult {
+    /// Aircraft from every tile that answered, one per `icao24`, sorted by it.
+    pub aircraft: Vec<Aircraft>,
+    /// Tiles whose request failed, with the error, in tile order.
+    pub failed: Vec<(BoundingBox, IfoError)>,
+}
+
+/// Take one retry from a shared budget; false once it is used up. Without
+/// a budget there is nothing to take from.
+fn take_retry(budget: Option<&AtomicU32>) -> bool {
+    budget.is_none_or(|budget| {
+        budget
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok()
+    })
+}
+
+/// How long a 429 response asks us to wait.
+///
+/// OpenSky sends `X-Rate-Limit-Retry-After-Seconds`; the standard
+/// `Retry-After` header is honoured too, in its delay-seconds form only.
+fn retry_after(response: &Response) -> Option<Duration> {
+    ["x-rate-limit-retry-after-seconds", "retry-after"]
+        .iter()
+        .find_map(|name| {
+            response
+                .headers()
+                .get(*name)?
+                .to_str()
+                .ok()?
+                .trim()
+                .parse()
+                .ok()
+        })
+        .map(Duration::from_secs)
+}
+
+/// Milliseconds since `started`, for tracing fields.
+pub(crate) fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+/// Builder for [`OpenSkyClient`].
+#[derive(Debug, Clone)]
+pub struct OpenSkyClientBuilder {
+    base_url: String,
+    bbox_params: BboxParamNames,
+    http: HttpOptions,
+    rate_limiter: Option<Arc<dyn Pacer>>,
+    rate_limit_retries: u32,
+    max_retry_wait: Duration,
+    tiled_retry_budget: u32,
+    cache_ttl: Option<Duration>,
+    cache_grid_deg: f64,
+}
+
+impl Default for OpenSkyClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            bbox_params: BboxParamNames::default(),
+            http: HttpOptions::new(DEFAULT_USER_AGENT),
+            rate_limiter: None,
+            rate_limit_retries: DEFAULT_RATE_LIMIT_RETRIES,
+            max_retry_wait: DEFAULT_MAX_RETRY_WAIT,
+            tiled_retry_budget: DEFAULT_TILED_RETRY_BUDGET,
+            cache_ttl: None,
+            cache_grid_deg: DEFAULT_CACHE_GRID_DEG,
+        }
+    }
+}
+
+impl OpenSkyClientBuilder {
+    /// Point the client at an OpenSky-compatible API other than the public one.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Use different query parameter names for the bounding box.
+    pub fn bbox_param_names(mut self, names: BboxParamNames) -> Self {
+        self.bbox_params = names;
+        self
+    }
+
+    /// Set the per-request timeout. Ignored on wasm32.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http.timeout = timeout;
+        self
+    }
+
+    /// Send `user_agent` as the `User-Agent` header instead of the default
+    /// `IFO-CLI/2.0 (Rust)`. Heavy users should include a contact address or
+    /// URL, as the data providers' usage policies ask.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.http.user_agent = user_agent.into();
+        self
+    }
+
+    /// Accept invalid TLS certificates, including self-signed ones.
+    ///
+    /// **DANGER:** this disables certificate verification entirely, leaving
+    /// every request open to interception. It exists only for talking to
+    /// self-hosted development servers and must never be enabled in
+    /// production. Defaults to `false`. Ignored on wasm32, where the browser
+    /// decides.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.http.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    /// Set how redirects are followed. Defaults to [`RedirectPolicy::Follow`].
+    /// Ignored on wasm32.
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.http.redirect_policy = policy;
+        self
+    }
+
+    /// Refuse response bodies larger than `bytes`, failing with
+    /// [`IfoError::ResponseTooLarge`] instead of buffering them. Guards
+    /// against untrusted mirrors; defaults to 50 MB.
+    pub fn max_response_bytes(mut self, bytes: usize) -> Self {
+        self.http.max_response_bytes = bytes;
+        self
+    }
+
+    /// Trust an additional root certificate, e.g. a corporate CA that
+    /// re-signs outgoing TLS traffic. May be called more than once.
+    ///
+    /// Load a PEM file with:
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let pem = std::fs::read("/etc/ssl/certs/corporate-ca.pem")?;
+    /// let cert = reqwest::Certificate::from_pem(&pem)?;
+    /// let client = ifo::api::OpenSkyClient::builder()
+    ///     .add_root_certificate(cert)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_root_certificate(mut self, cert: reqwest::Certificate) -> Self {
+        self.http.root_certificates.push(cert);
+        self
+    }
+
+    /// Pace every request through a token bucket.
+    ///
+    /// Pass the same `Arc` to several builders to enforce one request budget
+    /// across all of those clients. No limit is applied by default.
+    pub fn rate_limiter(self, limiter: Arc<DefaultDirectRateLimiter>) -> Self {
+        self.rate_limiter_with_clock(limiter, DefaultClock::default())
+    }
+
+    /// [`rate_limiter`](Self::rate_limiter) for a limiter on another clock,
+    /// such as governor's `FakeRelativeClock` in tests. `clock` must be the
+    /// one the limiter was built with, or a clone of it.
+    pub fn rate_limiter_with_clock<C>(
+        mut self,
+        limiter: Arc<RateLimiter<NotKeyed, InMemoryState, C, NoOpMiddleware<C::Instant>>>,
+        clock: C,
+    ) -> Self
+    where
+        C: Clock + fmt::Debug + Send + Sync + 'static,
+        C::Instant: Send + Sync,
+    {
+        self.rate_limiter = Some(Arc::new(ClockedLimiter { limiter, clock }));
+        self
+    }
+
+    /// How many times to retry a request OpenSky rejected with 429, after
+    /// waiting as long as it asks. Retries go through the
+    /// [`rate_limiter`](Self::rate_limiter) like any other request.
+    /// Defaults to 1; 0 fails straight away with
+    /// [`IfoError::RateLimitExceeded`].
+    pub fn rate_limit_retries(mut self, retries: u32) -> Self {
+        self.rate_limit_retries = retries;
+        self
+    }
+
+    /// Longest retry delay worth waiting for. A 429 asking for more, as
+    /// happens once the daily credits are spent, fails immediately with
+    /// [`IfoError::RateLimitExceeded`]. Defaults to 30 seconds.
+    pub fn max_retry_wait(mut self, wait: Duration) -> Self {
+        self.max_retry_wait = wait;
+        self
+    }
+
+    /// Total 429 retries allowed across all tiles of one tiled query
+    /// ([`stream_area_tiled`](OpenSkyClient::stream_area_tiled) or
+    /// [`get_aircraft_in_area_tiled`](OpenSkyClient::get_aircraft_in_area_tiled)).
+    ///
+    /// Each tile still retries at most
+    /// [`rate_limit_retries`](Self::rate_limit_retries) times, but once the
+    /// shared budget is spent further 429s fail straight away, so a
+    /// struggling server is not hit again for every tile. Defaults to 3.
+    pub fn tiled_retry_budget(mut self, retries: u32) -> Self {
+        self.tiled_retry_budget = retries;
+        self
+    }
+
+    /// Reuse a successful `/states/all` snapshot for `ttl` instead of asking
+    /// OpenSky again for the same area. Off by default.
+    ///
+    /// Clones of the client share the cache. Boxes are compared after
+    /// snapping their edges to [`cache_grid`](Self::cache_grid), so tiny
+    /// floating-point differences still hit.
+    pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Grid, in degrees, that bounding boxes are snapped to for the
+    /// [`cache_ttl`](Self::cache_ttl) cache. Defaults to 0.01° (about 1 km).
+    pub fn cache_grid(mut self, degrees: f64) -> Self {
+        self.cache_grid_deg = degrees;
+        self
+    }
+
+    /// Build the client.
+    pub fn build(self) -> Result<OpenSkyClient> {
+        if !(self.cache_grid_deg.is_finite() && self.cache_grid_deg > 0.0) {
+            return Err(IfoError::InvalidBoundingBox(format!(
+                "cache grid must be positive, got {}",
+                self.cache_grid_deg
+            )));
+        }
+        let cache = self.cache_ttl.map(|ttl| {
+            Arc::new(SnapshotCache {
+                ttl,
+                grid_deg: self.cache_grid_deg,
+                entries: Mutex::new(HashMap::new()),
+            })
+        });
+
+        Ok(OpenSkyClient {
+            client: self.http.build_client()?,
+            base_url: self.base_url,
+            bbox_params: self.bbox_params,
+            max_response_bytes: self.http.max_response_bytes,
+            rate_limiter: self.rate_limiter,
+            rate_limit_retries: self.rate_limit_retries,
+            max_retry_wait: self.max_retry_wait,
+            tiled_retry_budget: self.tiled_retry_budget,
+            cache,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::Coordinate;
+    use governor::{Quota, RateLimiter};
+    use mockito::{Matcher, Server};
+    use nonzero::nonzero;
+    use std::io;
+
+    #[test]
+    fn test_builder_accepts_invalid_certs_option() {
+        let client = OpenSkyClient::builder()
+            .base_url("https://localhost:8443/api/")
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url, "https://localhost:8443/api");
+    }
+
+    #[test]
+    fn test_builder_with_root_certificate() {
+        let cert = reqwest::Certificate::from_pem(include_bytes!("testdata/ca.pem")).unwrap();
+        assert!(OpenSkyClient::builder()
+            .add_root_certificate(cert)
+            .build()
+            .is_ok());
+    }
+
+    /// Writer collecting formatted tracing output into a shared buffer.
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_lifecycle_events() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"time": 1700000000, "states": [["abc123", "SHORT"]]}"#)
+            .create_async()
+            .await;
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        client.get_aircraft_in_area(bbox).await.unwrap();
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("query started"), "{output}");
+        assert!(output.contains("response received"), "{output}");
+        assert!(output.contains("count=0"), "{output}");
+        assert!(output.contains("elapsed_ms="), "{output}");
+        assert!(output.contains("bbox=BoundingBox"), "{output}");
+        assert!(
+            output.contains("WARN") && output.contains("failed to parse state vector"),
+            "{output}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_bbox_param_names() {
+        let mut server = Server::new_async().await;
+        let states = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("south".into(), "40".into()),
+                Matcher::UrlEncoded("west".into(), "0".into()),
+                Matcher::UrlEncoded("north".into(), "45".into()),
+                Matcher::UrlEncoded("east".into(), "10".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"time": 1700000000, "states": null}"#)
+            .create_async()
+            .await;
+
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .bbox_param_names(BboxParamNames {
+                lat_min: "south".to_string(),
+                lon_min: "west".to_string(),
+                lat_max: "north".to_string(),
+                lon_max: "east".to_string(),
+            })
+            .build()
+            .unwrap();
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        client.get_aircraft_in_area(bbox).await.unwrap();
+
+        states.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_header() {
+        let mut server = Server::new_async().await;
+        let ua = "ifo-mirror/1.0 (ops@example.com)";
+        let states = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .match_header("user-agent", ua)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"time": 1700000000, "states": null}"#)
+            .create_async()
+            .await;
+
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .user_agent(ua)
+            .build()
+            .unwrap();
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        client.get_aircraft_in_area(bbox).await.unwrap();
+
+        states.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_tiled_query_reports_failed_tiles() {
+        let mut server = Server::new_async().await;
+        let tile = |lat_min: &str| {
+            Matcher::AllOf(vec![
+                Matcher::UrlEncoded("lamin".into(), lat_min.into()),
+                Matcher::UrlEncoded("lomin".into(), "0".into()),
+            ])
+        };
+        server
+            .mock("GET", "/states/all")
+            .match_query(tile("40"))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"time": 1700000000, "states": [
+                    ["00000b", "SOUTH   ", "France", null, 100, 1.0, 41.0, 900.0, false, null, null, null, null, null, null, false, 0],
+                    ["00000a", "EDGE    ", "France", null, 100, 1.0, 42.0, 900.0, false, null, null, null, null, null, null, false, 0]
+                ]}"#,
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/states/all")
+            .match_query(tile("42"))
+            .with_status(503)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/states/all")
+            .match_query(tile("44"))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"time": 1700000000, "states": [
+                    ["00000a", "EDGE    ", "France", null, 120, 1.0, 44.0, 900.0, false, null, null, null, null, null, null, false, 0]
+                ]}"#,
+            )
+            .create_async()
+            .await;
+
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let bbox = BoundingBox::new(40.0, 0.0, 46.0, 2.0).unwrap();
+        let result = client.get_aircraft_in_area_tiled(bbox, 2.0).await.unwrap();
+
+        let icao24s: Vec<&str> = result
+            .aircraft
+            .iter()
+            .map(|ac| ac.icao24.as_str())
+            .collect();
+        assert_eq!(icao24s, ["00000a", "00000b"]);
+        assert_eq!(result.aircraft[0].last_contact, Some(120));
+        assert_eq!(result.failed.len(), 1);
+        let (failed_tile, error) = &result.failed[0];
+        assert_eq!(
+            *failed_tile,
+            BoundingBox::new(42.0, 0.0, 44.0, 2.0).unwrap()
+        );
+        assert!(matches!(error, IfoError::ApiError { status: 503, .. }));
+
+        assert!(matches!(
+            client.get_aircraft_in_area_tiled(bbox, -1.0).await,
+            Err(IfoError::InvalidBoundingBox(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tiled_query_progress() {
+        let mut server = Server::new_async().await;
+        let states = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"time": 1700000000, "states": null}"#)
+            .expect(6)
+            .create_async()
+            .await;
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        let tiles = bbox.tiles(4.0).unwrap().len();
+        let calls = Mutex::new(Vec::new());
+        client
+            .get_aircraft_in_area_tiled_with_progress(bbox, 4.0, |done, total| {
+                calls.lock().unwrap().push((done, total));
+            })
+            .await
+            .unwrap();
+
+        states.assert_async().await;
+        let calls = calls.into_inner().unwrap();
+        assert_eq!(calls.len(), tiles);
+        assert_eq!(calls, (1..=tiles).map(|i| (i, tiles)).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_tiled_retry_budget_is_shared() {
+        let mut server = Server::new_async().await;
+        let states = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_status(429)
+            .with_header("x-rate-limit-retry-after-seconds", "0")
+            .expect(4)
+            .create_async()
+            .await;
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .rate_limit_retries(2)
+            .tiled_retry_budget(1)
+            .build()
+            .unwrap();
+
+        // Three tiles that would each retry twice on their own: 3 first
+        // attempts plus the single retry the budget allows.
+        let bbox = BoundingBox::new(40.0, 0.0, 46.0, 2.0).unwrap();
+        let result = client.get_aircraft_in_area_tiled(bbox, 2.0).await.unwrap();
+
+        states.assert_async().await;
+        assert_eq!(result.failed.len(), 3);
+        assert!(result
+            .failed
+            .iter()
+            .all(|(_, e)| matches!(e, IfoError::RateLimitExceeded)));
+    }
+
+    #[tokio::test]
+    async fn test_html_maintenance_page() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "text/html; charset=utf-8")
+            .with_body("<html><body>Down for maintenance</body></html>")
+            .create_async()
+            .await;
+
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        match client.get_snapshot_in_area(bbox).await {
+            Err(IfoError::UnexpectedResponse { service }) => assert_eq!(service, "OpenSky"),
+            other => panic!("expected UnexpectedResponse, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redirect_policy() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_status(302)
+            .with_header("location", "/moved/states/all")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/moved/states/all")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"time": 1700000000, "states": null}"#)
+            .create_async()
+            .await;
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+
+        let following = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        assert!(following.get_aircraft_in_area(bbox).await.is_ok());
+
+        let strict = OpenSkyClient::builder()
+            .base_url(server.url())
+            .redirect_policy(RedirectPolicy::None)
+            .build()
+            .unwrap();
+        let result = strict.get_aircraft_in_area(bbox).await;
+        assert!(matches!(
+            result,
+            Err(IfoError::ApiError { status: 302, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cache_reuses_recent_snapshot() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"time": 1700000000, "states": [
+                    ["000001", null, "", null, null, 2.0, 42.0, null, false, null, null, null, null, null, null, false, 0]
+                ]}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .cache_ttl(Duration::from_secs(60))
+            .build()
+            .unwrap();
+
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        let first = client.get_snapshot_in_area(bbox).await.unwrap();
+        // Float noise well inside the grid, through a clone: still a hit.
+        let nudged = BoundingBox::new(40.000001, 0.0, 45.0, 10.0).unwrap();
+        let second = client.clone().get_snapshot_in_area(nudged).await.unwrap();
+        assert_eq!(second.time, first.time);
+        assert_eq!(second.aircraft.len(), 1);
+
+        // A different area is fetched.
+        let other = BoundingBox::new(40.0, 0.0, 45.0, 11.0).unwrap();
+        client.get_snapshot_in_area(other).await.unwrap();
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_stream_area_tiled() {
+        let mut server = Server::new_async().await;
+        let west = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::UrlEncoded("lomax".into(), "5".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"time": 1700000000, "states": [
+                    ["000001", null, "", null, null, 2.0, 42.0, null, false, null, null, null, null, null, null, false, 0]
+                ]}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let east = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::UrlEncoded("lomin".into(), "5".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"time": 1700000000, "states": [
+                    ["000002", null, "", null, null, 7.0, 42.0, null, false, null, null, null, null, null, null, false, 0],
+                    ["000003", null, "", null, null, 8.0, 43.0, null, false, null, null, null, null, null, null, false, 0]
+                ]}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        let mut tiles = Box::pin(client.stream_area_tiled(bbox, 5.0));
+
+        let first = tiles.next().await.unwrap().unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].icao24, "000001");
+        assert!(!east.matched_async().await);
+
+        let second = tiles.next().await.unwrap().unwrap();
+        assert_eq!(second.len(), 2);
+        assert!(tiles.next().await.is_none());
+
+        west.assert_async().await;
+        east.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_polygon_filters_aircraft() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("lamin".into(), "0".into()),
+                Matcher::UrlEncoded("lomin".into(), "0".into()),
+                Matcher::UrlEncoded("lamax".into(), "10".into()),
+                Matcher::UrlEncoded("lomax".into(), "10".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"time": 1700000000, "states": [
+                    ["000001", null, "", null, null, 2.0, 2.0, null, false, null, null, null, null, null, null, false, 0],
+                    ["000002", null, "", null, null, 6.0, 6.0, null, false, null, null, null, null, null, null, false, 0],
+                    ["000003", null, "", null, null, null, null, null, false, null, null, null, null, null, null, false, 0]
+                ]}"#,
+            )
+            .create_async()
+            .await;
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let triangle = [
+            Coordinate::new(0.0, 0.0).unwrap(),
+            Coordinate::new(0.0, 10.0).unwrap(),
+            Coordinate::new(10.0, 0.0).unwrap(),
+        ];
+        let aircraft = client.get_aircraft_in_polygon(&triangle).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(aircraft.len(), 1);
+        assert_eq!(aircraft[0].icao24, "000001");
+    }
+
+    #[tokio::test]
+    async fn test_flights_by_aircraft() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/flights/aircraft")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("icao24".into(), "3c6444".into()),
+                Matcher::UrlEncoded("begin".into(), "1700000000".into()),
+                Matcher::UrlEncoded("end".into(), "1700086400".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[
+                    {"icao24": "3c6444", "firstSeen": 1700003600, "estDepartureAirport": "EDDF",
+                     "lastSeen": 1700010800, "estArrivalAirport": "EGLL", "callsign": "DLH900  ",
+                     "estDepartureAirportHorizDistance": 1200, "arrivalAirportCandidatesCount": 1},
+                    {"icao24": "3c6444", "firstSeen": 1700014400, "estDepartureAirport": "EGLL",
+                     "lastSeen": 1700021600, "estArrivalAirport": null, "callsign": null}
+                ]"#,
+            )
+            .create_async()
+            .await;
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let flights = client
+            .get_flights_by_aircraft("3C6444", 1_700_000_000, 1_700_086_400)
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(flights.len(), 2);
+        assert_eq!(flights[0].callsign.as_deref(), Some("DLH900"));
+        assert_eq!(flights[0].est_arrival_airport.as_deref(), Some("EGLL"));
+        assert_eq!(flights[1].first_seen, 1_700_014_400);
+        assert!(flights[1].est_arrival_airport.is_none());
+
+        let too_long = client
+            .get_flights_by_aircraft("3c6444", 1_700_000_000, 1_700_000_000 + 3 * 86_400)
+            .await;
+        assert!(matches!(too_long, Err(IfoError::InvalidTimeWindow(_))));
+    }
+
+    #[tokio::test]
+    async fn test_gzip_response_is_decoded() {
+        use flate2::{write::GzEncoder, Compression};
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        io::Write::write_all(
+            &mut encoder,
+            br#"{"time": 1700000000, "states": [
+                ["abc123", "UAL123  ", "United States", null, null, 5.0, 42.0, null, false, null, null, null, null, null, null, false, 0]
+            ]}"#,
+        )
+        .unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .match_header("accept-encoding", Matcher::Regex("gzip".into()))
+            .with_header("content-type", "application/json")
+            .with_header("content-encoding", "gzip")
+            .with_body(gzipped)
+            .create_async()
+            .await;
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        let aircraft = client.get_aircraft_in_area(bbox).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(aircraft.len(), 1);
+        assert_eq!(aircraft[0].icao24, "abc123");
+    }
+
+    #[tokio::test]
+    async fn test_max_response_bytes() {
+        let mut server = Server::new_async().await;
+        let body = format!(
+            r#"{{"time": 1700000000, "states": [], "padding": "{}"}}"#,
+            "x".repeat(4096)
+        );
+        server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(&body)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/flights/aircraft")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_chunked_body(|w| {
+                // No Content-Length, so the running count has to catch it.
+                for _ in 0..64 {
+                    w.write_all(&[b' '; 128])?;
+                }
+                w.write_all(b"[]")
+            })
+            .create_async()
+            .await;
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+
+        let strict = OpenSkyClient::builder()
+            .base_url(server.url())
+            .max_response_bytes(1024)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            strict.get_aircraft_in_area(bbox).await,
+            Err(IfoError::ResponseTooLarge { limit: 1024 })
+        ));
+        assert!(matches!(
+            strict.get_flights_by_aircraft("3c6444", 0, 3600).await,
+            Err(IfoError::ResponseTooLarge { limit: 1024 })
+        ));
+
+        let default = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        assert!(default.get_aircraft_in_area(bbox).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_in_flight_request() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(500));
+                w.write_all(br#"{"time": 1700000000, "states": []}"#)
+            })
+            .create_async()
+            .await;
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceller.cancel();
+        });
+
+        let bbox = BoundingBox::new(37.0, -123.0, 38.0, -122.0).unwrap();
+        let started = Instant::now();
+        let result = client.get_aircraft_in_area_cancellable(bbox, token).await;
+
+        assert!(matches!(result, Err(IfoError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn test_retries_after_rate_limit() {
+        let mut server = Server::new_async().await;
+        let limited = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_status(429)
+            .with_header("retry-after", "1")
+            .expect(1)
+            .create_async()
+            .await;
+        let states = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"time": 1700000000, "states": null}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        // One request per 1.2s on a clock that only moves when told to
+        let clock = governor::clock::FakeRelativeClock::default();
+        let quota = Quota::with_period(Duration::from_millis(1200)).unwrap();
+        let limiter = Arc::new(RateLimiter::direct_with_clock(quota, &clock));
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .rate_limiter_with_clock(limiter.clone(), clock.clone())
+            .build()
+            .unwrap();
+
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        let start = Instant::now();
+        let snapshot = client.get_snapshot_in_area(bbox);
+        tokio::pin!(snapshot);
+
+        // The server's one-second delay passes, but the retry still needs a
+        // cell of its own and the fake clock hasn't freed one.
+        assert!(tokio::time::timeout(Duration::from_secs(2), &mut snapshot)
+            .await
+            .is_err());
+        limited.assert_async().await;
+        assert!(!states.matched_async().await);
+
+        clock.advance(Duration::from_millis(1200));
+        let snapshot = tokio::time::timeout(Duration::from_secs(5), snapshot)
+            .await
+            .expect("retry once the fake clock frees a cell")
+            .unwrap();
+
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(snapshot.time, Some(1700000000));
+        assert!(limiter.check().is_err());
+        states.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_beyond_max_wait_fails_fast() {
+        let mut server = Server::new_async().await;
+        let limited = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_status(429)
+            .with_header("x-rate-limit-retry-after-seconds", "3600")
+            .expect(1)
+            .create_async()
+            .await;
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        let result = client.get_snapshot_in_area(bbox).await;
+
+        assert!(matches!(result, Err(IfoError::RateLimitExceeded)));
+        limited.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_shared_rate_limiter_paces_requests() {
+        let mut server = Server::new_async().await;
+        let states = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"time": 1700000000, "states": null}"#)
+            .expect(4)
+            .create_async()
+            .await;
+
+        let quota = Quota::with_period(Duration::from_millis(100))
+            .unwrap()
+            .allow_burst(nonzero!(1u32));
+        let limiter = Arc::new(RateLimiter::direct(quota));
+        let first = OpenSkyClient::builder()
+            .base_url(server.url())
+            .rate_limiter(limiter.clone())
+            .build()
+            .unwrap();
+        let second = OpenSkyClient::builder()
+            .base_url(server.url())
+            .rate_limiter(limiter)
+            .build()
+            .unwrap();
+
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        let start = Instant::now();
+        for client in [&first, &second, &first, &second] {
+            client.get_aircraft_in_area(bbox).await.unwrap();
+        }
+
+        // One burst token, then one request per 100ms for the other three
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        states.assert_async().await;
+    }
 
     #[tokio::test]
     async fn test_bounding_box_validation() {
@@ -3,6 +3,7 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum IfoError {
     #[error("Invalid coordinates: {0}")]
     InvalidCoordinates(String),
@@ -16,6 +17,15 @@ pub enum IfoError {
     #[error("Invalid bounding box: {0}")]
     InvalidBoundingBox(String),
 
+    #[error("Invalid time window: {0}")]
+    InvalidTimeWindow(String),
+
+    #[error("Invalid ICAO24 address: {0:?} (expected 6 hex digits)")]
+    InvalidIcao24(String),
+
+    #[error("Unknown aircraft field: {0}")]
+    UnknownField(String),
+
     #[error("Place name cannot be empty")]
     EmptyPlaceName,
 
@@ -25,15 +35,26 @@ pub enum IfoError {
     #[error("Could not find location: {0}")]
     LocationNotFound(String),
 
+    #[error("No location given: set a place name or coordinates")]
+    MissingLocation,
+
+    #[cfg(feature = "net")]
     #[error("Network request failed: {0}")]
     NetworkError(#[from] reqwest::Error),
 
     #[error("API request failed with status {status}: {message}")]
     ApiError { status: u16, message: String },
 
+    #[error("Response body larger than {limit} bytes")]
+    ResponseTooLarge { limit: usize },
+
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[cfg(feature = "msgpack")]
+    #[error("MessagePack encoding error: {0}")]
+    MsgpackError(#[from] rmp_serde::encode::Error),
+
     #[error("Unexpected response format from {service}")]
     UnexpectedResponse { service: String },
 
@@ -46,6 +67,9 @@ pub enum IfoError {
     #[error("Timeout after {seconds} seconds")]
     Timeout { seconds: u64 },
 
+    #[error("Request cancelled")]
+    Cancelled,
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
@@ -1,122 +1,614 @@
 //! Geocoding module for converting place names to coordinates.
 //!
 //! Uses Nominatim (OpenStreetMap) geocoding service which is free
-//! and doesn't require an API key.
+//! and doesn't require an API key. [`PhotonGeocoder`] offers Photon, another
+//! OpenStreetMap-based service, behind the same [`GeocoderBackend`] trait.
 
+use governor::clock::Clock;
+use governor::middleware::NoOpMiddleware;
+use governor::state::{InMemoryState, NotKeyed};
 use governor::{Jitter, Quota, RateLimiter};
 use nonzero::nonzero;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Url};
+use serde::Deserialize;
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
+use tracing::{debug, info, instrument, warn};
+use web_time::Instant;
 
+use crate::api::{
+    elapsed_ms, read_error_text, read_json, sleep, DefaultClock, HttpOptions, RedirectPolicy,
+};
 use crate::error::{IfoError, Result};
-use crate::models::{Location, NominatimResult};
+use crate::models::{BoundingBox, Coordinate, Location, NominatimResult, PhotonResponse};
+use crate::source::MaybeSend;
 
 const MAX_PLACE_LENGTH: usize = 200;
+const DEFAULT_BASE_URL: &str = "https://nominatim.openstreetmap.org";
+const DEFAULT_USER_AGENT: &str = "IFO-CLI/2.0 (Aircraft tracking tool)";
+const DEFAULT_PHOTON_BASE_URL: &str = "https://photon.komoot.io";
+
+/// A geocoding service: place names to coordinates and back.
+///
+/// The query pipeline is written against this trait, so Nominatim
+/// ([`Geocoder`]) and Photon ([`PhotonGeocoder`]) are interchangeable.
+pub trait GeocoderBackend {
+    /// Best match for a place name, or `None` if nothing matched.
+    fn geocode(&self, place: &str) -> impl Future<Output = Result<Option<Location>>> + MaybeSend;
+
+    /// Nearest named place to `coord`, or `None` if there is none.
+    fn reverse(
+        &self,
+        coord: Coordinate,
+    ) -> impl Future<Output = Result<Option<Location>>> + MaybeSend;
+
+    /// The URL [`geocode`](Self::geocode) would request for `place`,
+    /// without sending anything.
+    fn search_url(&self, place: &str) -> Result<Url>;
+}
 
 /// Geocoder using Nominatim API with rate limiting.
-pub struct Geocoder {
+///
+/// The rate limiter runs on the clock `C`, the real clock unless another
+/// one is set with [`GeocoderBuilder::clock`].
+///
+/// Clones are cheap and share the HTTP connection pool and the rate
+/// limiter, so requests from every clone count against the same quota.
+#[derive(Clone)]
+pub struct Geocoder<C: Clock = DefaultClock> {
     client: Client,
     base_url: String,
-    rate_limiter: Arc<
-        Mutex<
-            RateLimiter<
-                governor::state::NotKeyed,
-                governor::state::InMemoryState,
-                governor::clock::DefaultClock,
-            >,
-        >,
-    >,
+    min_importance: Option<f64>,
+    max_response_bytes: usize,
+    clock: C,
+    rate_limiter: Arc<RateLimiter<NotKeyed, InMemoryState, C, NoOpMiddleware<C::Instant>>>,
 }
 
 impl Geocoder {
     /// Create a new geocoder with rate limiting.
     pub fn new(timeout_secs: u64) -> Result<Self> {
-        let client = Client::builder()
-            .user_agent("IFO-CLI/2.0 (Aircraft tracking tool)")
+        Self::builder()
             .timeout(Duration::from_secs(timeout_secs))
             .build()
-            .map_err(IfoError::NetworkError)?;
-
-        // Create rate limiter: 1 request per second (Nominatim policy)
-        let quota = Quota::per_second(nonzero!(1u32));
-        let rate_limiter = RateLimiter::direct(quota);
+    }
 
-        Ok(Self {
-            client,
-            base_url: "https://nominatim.openstreetmap.org".to_string(),
-            rate_limiter: Arc::new(Mutex::new(rate_limiter)),
-        })
+    /// Start configuring a geocoder with non-default options.
+    pub fn builder() -> GeocoderBuilder {
+        GeocoderBuilder::default()
     }
+}
 
+impl<C: Clock> Geocoder<C> {
     /// Convert a place name to coordinates.
     pub async fn geocode(&self, place: &str) -> Result<Option<Location>> {
-        // Validate input
-        let place = place.trim();
-        if place.is_empty() {
-            return Err(IfoError::EmptyPlaceName);
-        }
-        if place.len() > MAX_PLACE_LENGTH {
-            return Err(IfoError::PlaceNameTooLong {
-                max: MAX_PLACE_LENGTH,
+        self.search(place, &[]).await
+    }
+
+    /// Convert a place name to coordinates, preferring results inside `bbox`.
+    ///
+    /// With `bounded` set, results outside the box are excluded entirely,
+    /// so an ambiguous name like "Springfield" resolves to the one you mean
+    /// or to nothing.
+    pub async fn geocode_in_box(
+        &self,
+        place: &str,
+        bbox: BoundingBox,
+        bounded: bool,
+    ) -> Result<Option<Location>> {
+        // Nominatim wants two opposite corners as x1,y1,x2,y2.
+        let viewbox = format!(
+            "{},{},{},{}",
+            bbox.lon_min, bbox.lat_max, bbox.lon_max, bbox.lat_min
+        );
+        let bounded = if bounded { "1" } else { "0" };
+        self.search(place, &[("viewbox", &viewbox), ("bounded", bounded)])
+            .await
+    }
+
+    /// Find the nearest named place to `coord`.
+    #[instrument(name = "nominatim_reverse", skip(self))]
+    pub async fn reverse(&self, coord: Coordinate) -> Result<Option<Location>> {
+        let url = format!("{}/reverse", self.base_url);
+        let request = self.client.get(&url).query(&[
+            ("lat", coord.latitude.to_string()),
+            ("lon", coord.longitude.to_string()),
+            ("format", "json".to_string()),
+        ]);
+
+        self.wait_for_permit().await;
+
+        debug!("query started");
+        let started = Instant::now();
+        let response = request.send().await.inspect_err(|e| {
+            warn!(error = %e, elapsed_ms = elapsed_ms(started), "request failed");
+        })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            warn!(
+                status = status.as_u16(),
+                elapsed_ms = elapsed_ms(started),
+                "request failed"
+            );
+            let text = read_error_text(response, self.max_response_bytes).await;
+            return Err(IfoError::ApiError {
+                status: status.as_u16(),
+                message: format!("Reverse geocoding failed: {}", text),
             });
         }
 
-        // Rate limiting: wait for permission
-        {
-            let limiter = self.rate_limiter.lock().await;
-            // Add jitter to avoid thundering herd
-            limiter
-                .until_ready_with_jitter(Jitter::up_to(Duration::from_millis(100)))
-                .await;
+        let result: ReverseResult = read_json(response, self.max_response_bytes).await?;
+        info!(elapsed_ms = elapsed_ms(started), "response received");
+        match result {
+            ReverseResult::Found(result) => Ok(Some(nominatim_location(&result)?)),
+            ReverseResult::NotFound(_) => Ok(None),
         }
+    }
+
+    #[instrument(name = "nominatim_search", skip(self, extra_params))]
+    async fn search(&self, place: &str, extra_params: &[(&str, &str)]) -> Result<Option<Location>> {
+        let request = self.search_request(place, extra_params)?;
+
+        self.wait_for_permit().await;
 
         // Make request
+        debug!("query started");
+        let started = Instant::now();
+        let response = request.send().await.inspect_err(|e| {
+            warn!(error = %e, elapsed_ms = elapsed_ms(started), "request failed");
+        })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            warn!(
+                status = status.as_u16(),
+                elapsed_ms = elapsed_ms(started),
+                "request failed"
+            );
+            let text = read_error_text(response, self.max_response_bytes).await;
+            return Err(IfoError::ApiError {
+                status: status.as_u16(),
+                message: format!("Geocoding failed: {}", text),
+            });
+        }
+
+        let results: Vec<NominatimResult> = read_json(response, self.max_response_bytes).await?;
+        info!(
+            count = results.len(),
+            elapsed_ms = elapsed_ms(started),
+            "response received"
+        );
+
+        if results.is_empty() {
+            return Ok(None);
+        }
+
+        let result = &results[0];
+        let location = nominatim_location(result)?;
+
+        if let (Some(min), Some(importance)) = (self.min_importance, result.importance) {
+            if importance < min {
+                warn!(
+                    importance,
+                    min_importance = min,
+                    display_name = %result.display_name,
+                    "discarding low-importance match"
+                );
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(location))
+    }
+
+    /// The full URL [`geocode`](Self::geocode) would request for `place`,
+    /// without sending anything.
+    pub fn search_url(&self, place: &str) -> Result<Url> {
+        Ok(self.search_request(place, &[])?.build()?.url().clone())
+    }
+
+    /// Validate `place` and prepare the search request for it.
+    fn search_request(&self, place: &str, extra_params: &[(&str, &str)]) -> Result<RequestBuilder> {
+        let place = validate_place(place)?;
         let url = format!("{}/search", self.base_url);
-        let response = self
+        Ok(self
             .client
             .get(&url)
             .query(&[("q", place), ("format", "json"), ("limit", "1")])
-            .send()
-            .await?;
+            .query(extra_params))
+    }
+
+    /// Wait until the rate limiter allows another request.
+    ///
+    /// The limiter is only ever asked for the remaining wait, measured on
+    /// its own clock; the sleeping itself is done by Tokio, so a fake clock
+    /// decides when a request may go without any real time passing.
+    async fn wait_for_permit(&self) {
+        // Add jitter to avoid thundering herd
+        let jitter = Jitter::up_to(Duration::from_millis(100));
+        while let Err(not_until) = self.rate_limiter.check() {
+            let wait = not_until.wait_time_from(self.clock.now());
+            sleep(jitter + wait).await;
+        }
+    }
+}
+
+impl<C> GeocoderBackend for Geocoder<C>
+where
+    C: Clock + Send + Sync,
+    C::Instant: Send + Sync,
+{
+    fn geocode(&self, place: &str) -> impl Future<Output = Result<Option<Location>>> + MaybeSend {
+        Geocoder::geocode(self, place)
+    }
+
+    fn reverse(
+        &self,
+        coord: Coordinate,
+    ) -> impl Future<Output = Result<Option<Location>>> + MaybeSend {
+        Geocoder::reverse(self, coord)
+    }
+
+    fn search_url(&self, place: &str) -> Result<Url> {
+        Geocoder::search_url(self, place)
+    }
+}
+
+/// Body of a Nominatim reverse lookup, which is a single object rather
+/// than a list.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReverseResult {
+    Found(NominatimResult),
+    /// `{"error": "Unable to geocode"}` when nothing is near.
+    NotFound(serde::de::IgnoredAny),
+}
+
+/// Trim a place name and check it is worth sending.
+fn validate_place(place: &str) -> Result<&str> {
+    let place = place.trim();
+    if place.is_empty() {
+        return Err(IfoError::EmptyPlaceName);
+    }
+    if place.len() > MAX_PLACE_LENGTH {
+        return Err(IfoError::PlaceNameTooLong {
+            max: MAX_PLACE_LENGTH,
+        });
+    }
+    Ok(place)
+}
+
+/// Convert a Nominatim result, parsing its coordinates.
+fn nominatim_location(result: &NominatimResult) -> Result<Location> {
+    Ok(Location {
+        lat: parse_degrees(&result.lat)?,
+        lon: parse_degrees(&result.lon)?,
+        display_name: result.display_name.clone(),
+        class: result.class.clone(),
+        place_type: result.place_type.clone(),
+        importance: result.importance,
+    })
+}
+
+/// Parse a latitude or longitude as returned by Nominatim.
+///
+/// Some mirrors format numbers for their locale, so a single comma is
+/// accepted as the decimal separator when there is no dot.
+fn parse_degrees(value: &str) -> Result<f64> {
+    let value = value.trim();
+    let parsed = match value.parse::<f64>() {
+        Ok(degrees) => Ok(degrees),
+        Err(e) if value.matches(',').count() == 1 && !value.contains('.') => {
+            value.replace(',', ".").parse::<f64>().map_err(|_| e)
+        }
+        Err(e) => Err(e),
+    };
+    parsed.map_err(|_| {
+        IfoError::InvalidCoordinates(format!("Nominatim returned unparseable number {:?}", value))
+    })
+}
+
+/// Builder for [`Geocoder`].
+#[derive(Debug, Clone)]
+pub struct GeocoderBuilder<C: Clock = DefaultClock> {
+    base_url: String,
+    http: HttpOptions,
+    min_importance: Option<f64>,
+    clock: C,
+}
+
+impl Default for GeocoderBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            http: HttpOptions::new(DEFAULT_USER_AGENT),
+            min_importance: None,
+            clock: DefaultClock::default(),
+        }
+    }
+}
+
+impl<C: Clock> GeocoderBuilder<C> {
+    /// Point the geocoder at a Nominatim instance other than the public one.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Treat a top result whose Nominatim `importance` is below `min` as no
+    /// match, so obscure queries don't resolve to an unrelated place.
+    /// Results without an importance are kept. Off by default.
+    pub fn min_importance(mut self, min: f64) -> Self {
+        self.min_importance = Some(min);
+        self
+    }
+
+    /// Set the per-request timeout. Ignored on wasm32.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http.timeout = timeout;
+        self
+    }
+
+    /// Send `user_agent` as the `User-Agent` header. Nominatim's usage
+    /// policy asks for one that identifies the application and, for heavy
+    /// use, a way to contact its operator; defaults to
+    /// `IFO-CLI/2.0 (Aircraft tracking tool)`.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.http.user_agent = user_agent.into();
+        self
+    }
+
+    /// Drive the rate limiter from `clock` instead of the real clock, e.g.
+    /// governor's `FakeRelativeClock` in tests.
+    pub fn clock<C2: Clock>(self, clock: C2) -> GeocoderBuilder<C2> {
+        GeocoderBuilder {
+            base_url: self.base_url,
+            http: self.http,
+            min_importance: self.min_importance,
+            clock,
+        }
+    }
+
+    /// Accept invalid TLS certificates, including self-signed ones.
+    ///
+    /// **DANGER:** this disables certificate verification entirely, leaving
+    /// every request open to interception. Only use it against a
+    /// self-hosted development instance, never in production. Defaults to
+    /// `false`. Ignored on wasm32, where the browser decides.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.http.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    /// Set how redirects are followed. Defaults to [`RedirectPolicy::Follow`].
+    /// Ignored on wasm32.
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.http.redirect_policy = policy;
+        self
+    }
+
+    /// Refuse response bodies larger than `bytes`. See
+    /// [`OpenSkyClientBuilder::max_response_bytes`](crate::api::OpenSkyClientBuilder::max_response_bytes).
+    pub fn max_response_bytes(mut self, bytes: usize) -> Self {
+        self.http.max_response_bytes = bytes;
+        self
+    }
+
+    /// Trust an additional root certificate, e.g. a corporate CA. See
+    /// [`OpenSkyClientBuilder::add_root_certificate`](crate::api::OpenSkyClientBuilder::add_root_certificate)
+    /// for loading one from a PEM file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_root_certificate(mut self, cert: reqwest::Certificate) -> Self {
+        self.http.root_certificates.push(cert);
+        self
+    }
+
+    /// Build the geocoder.
+    pub fn build(self) -> Result<Geocoder<C>> {
+        let client = self.http.build_client()?;
+
+        // Create rate limiter: 1 request per second (Nominatim policy)
+        let quota = Quota::per_second(nonzero!(1u32));
+        let rate_limiter = RateLimiter::direct_with_clock(quota, &self.clock);
+
+        Ok(Geocoder {
+            client,
+            base_url: self.base_url,
+            min_importance: self.min_importance,
+            max_response_bytes: self.http.max_response_bytes,
+            clock: self.clock,
+            rate_limiter: Arc::new(rate_limiter),
+        })
+    }
+}
+
+/// Geocoder using the Photon API, an OpenStreetMap-based alternative to
+/// Nominatim with better fuzzy matching.
+///
+/// Photon's public instance asks for fair use rather than a fixed rate, so
+/// unlike [`Geocoder`] no rate limiter is applied.
+#[derive(Clone)]
+pub struct PhotonGeocoder {
+    client: Client,
+    base_url: String,
+    max_response_bytes: usize,
+}
+
+impl PhotonGeocoder {
+    /// Create a geocoder for the public Photon instance.
+    pub fn new(timeout_secs: u64) -> Result<Self> {
+        Self::builder()
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+    }
+
+    /// Start configuring a Photon geocoder with non-default options.
+    pub fn builder() -> PhotonGeocoderBuilder {
+        PhotonGeocoderBuilder::default()
+    }
+
+    /// Convert a place name to coordinates.
+    #[instrument(name = "photon_search", skip(self))]
+    pub async fn geocode(&self, place: &str) -> Result<Option<Location>> {
+        let request = self.search_request(place)?;
+        self.first_match(request).await
+    }
+
+    /// Find the nearest named place to `coord`.
+    #[instrument(name = "photon_reverse", skip(self))]
+    pub async fn reverse(&self, coord: Coordinate) -> Result<Option<Location>> {
+        let url = format!("{}/reverse", self.base_url);
+        let request = self.client.get(&url).query(&[
+            ("lat", coord.latitude.to_string()),
+            ("lon", coord.longitude.to_string()),
+            ("limit", "1".to_string()),
+        ]);
+        self.first_match(request).await
+    }
+
+    /// The full URL [`geocode`](Self::geocode) would request for `place`,
+    /// without sending anything.
+    pub fn search_url(&self, place: &str) -> Result<Url> {
+        Ok(self.search_request(place)?.build()?.url().clone())
+    }
+
+    fn search_request(&self, place: &str) -> Result<RequestBuilder> {
+        let place = validate_place(place)?;
+        let url = format!("{}/api", self.base_url);
+        Ok(self.client.get(&url).query(&[("q", place), ("limit", "1")]))
+    }
+
+    /// Send a Photon request and convert its first feature.
+    async fn first_match(&self, request: RequestBuilder) -> Result<Option<Location>> {
+        debug!("query started");
+        let started = Instant::now();
+        let response = request.send().await.inspect_err(|e| {
+            warn!(error = %e, elapsed_ms = elapsed_ms(started), "request failed");
+        })?;
 
         if !response.status().is_success() {
             let status = response.status();
-            let text = response.text().await.unwrap_or_default();
+            warn!(
+                status = status.as_u16(),
+                elapsed_ms = elapsed_ms(started),
+                "request failed"
+            );
+            let text = read_error_text(response, self.max_response_bytes).await;
             return Err(IfoError::ApiError {
                 status: status.as_u16(),
                 message: format!("Geocoding failed: {}", text),
             });
         }
 
-        let results: Vec<NominatimResult> = response.json().await?;
+        let results: PhotonResponse = read_json(response, self.max_response_bytes).await?;
+        info!(
+            count = results.features.len(),
+            elapsed_ms = elapsed_ms(started),
+            "response received"
+        );
 
-        if results.is_empty() {
-            return Ok(None);
+        results
+            .features
+            .first()
+            .map(|feature| feature.to_location())
+            .transpose()
+    }
+}
+
+impl GeocoderBackend for PhotonGeocoder {
+    fn geocode(&self, place: &str) -> impl Future<Output = Result<Option<Location>>> + MaybeSend {
+        PhotonGeocoder::geocode(self, place)
+    }
+
+    fn reverse(
+        &self,
+        coord: Coordinate,
+    ) -> impl Future<Output = Result<Option<Location>>> + MaybeSend {
+        PhotonGeocoder::reverse(self, coord)
+    }
+
+    fn search_url(&self, place: &str) -> Result<Url> {
+        PhotonGeocoder::search_url(self, place)
+    }
+}
+
+/// Builder for [`PhotonGeocoder`].
+#[derive(Debug, Clone)]
+pub struct PhotonGeocoderBuilder {
+    base_url: String,
+    http: HttpOptions,
+}
+
+impl Default for PhotonGeocoderBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_PHOTON_BASE_URL.to_string(),
+            http: HttpOptions::new(DEFAULT_USER_AGENT),
         }
+    }
+}
 
-        let result = &results[0];
+impl PhotonGeocoderBuilder {
+    /// Point the geocoder at a self-hosted Photon instance.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
 
-        // Parse and validate response
-        let lat = result
-            .lat
-            .parse::<f64>()
-            .map_err(|_| IfoError::UnexpectedResponse {
-                service: "Nominatim".to_string(),
-            })?;
-        let lon = result
-            .lon
-            .parse::<f64>()
-            .map_err(|_| IfoError::UnexpectedResponse {
-                service: "Nominatim".to_string(),
-            })?;
+    /// Set the per-request timeout. Ignored on wasm32.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http.timeout = timeout;
+        self
+    }
 
-        Ok(Some(Location {
-            lat,
-            lon,
-            display_name: result.display_name.clone(),
-        }))
+    /// Send `user_agent` as the `User-Agent` header. See
+    /// [`GeocoderBuilder::user_agent`].
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.http.user_agent = user_agent.into();
+        self
+    }
+
+    /// Accept invalid TLS certificates, including self-signed ones.
+    ///
+    /// **DANGER:** this disables certificate verification entirely. See
+    /// [`GeocoderBuilder::danger_accept_invalid_certs`]. Defaults to `false`.
+    /// Ignored on wasm32, where the browser decides.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.http.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    /// Set how redirects are followed. Defaults to [`RedirectPolicy::Follow`].
+    /// Ignored on wasm32.
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.http.redirect_policy = policy;
+        self
+    }
+
+    /// Refuse response bodies larger than `bytes`. See
+    /// [`OpenSkyClientBuilder::max_response_bytes`](crate::api::OpenSkyClientBuilder::max_response_bytes).
+    pub fn max_response_bytes(mut self, bytes: usize) -> Self {
+        self.http.max_response_bytes = bytes;
+        self
+    }
+
+    /// Trust an additional root certificate, e.g. a corporate CA. See
+    /// [`OpenSkyClientBuilder::add_root_certificate`](crate::api::OpenSkyClientBuilder::add_root_certificate)
+    /// for loading one from a PEM file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_root_certificate(mut self, cert: reqwest::Certificate) -> Self {
+        self.http.root_certificates.push(cert);
+        self
+    }
+
+    /// Build the geocoder.
+    pub fn build(self) -> Result<PhotonGeocoder> {
+        Ok(PhotonGeocoder {
+            client: self.http.build_client()?,
+            base_url: self.base_url,
+            max_response_bytes: self.http.max_response_bytes,
+        })
     }
 }
 
@@ -124,6 +616,327 @@ impl Geocoder {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_builder_accepts_invalid_certs_option() {
+        let geocoder = Geocoder::builder()
+            .base_url("https://localhost:8080/")
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        assert_eq!(geocoder.base_url, "https://localhost:8080");
+    }
+
+    #[test]
+    fn test_builder_with_root_certificate() {
+        let cert = reqwest::Certificate::from_pem(include_bytes!("testdata/ca.pem")).unwrap();
+        assert!(Geocoder::builder()
+            .add_root_certificate(cert.clone())
+            .build()
+            .is_ok());
+        assert!(PhotonGeocoder::builder()
+            .add_root_certificate(cert)
+            .danger_accept_invalid_certs(true)
+            .build()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_redirect_policy_none() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/search")
+            .match_query(mockito::Matcher::Any)
+            .with_status(301)
+            .with_header("location", "https://example.com/search")
+            .create_async()
+            .await;
+
+        let geocoder = Geocoder::builder()
+            .base_url(server.url())
+            .redirect_policy(RedirectPolicy::None)
+            .build()
+            .unwrap();
+        let result = geocoder.geocode("London").await;
+        assert!(matches!(
+            result,
+            Err(IfoError::ApiError { status: 301, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_photon_redirect_policy_none() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/api")
+            .match_query(mockito::Matcher::Any)
+            .with_status(302)
+            .with_header("location", "https://example.com/api")
+            .create_async()
+            .await;
+
+        let geocoder = PhotonGeocoder::builder()
+            .base_url(server.url())
+            .redirect_policy(RedirectPolicy::None)
+            .build()
+            .unwrap();
+        let result = geocoder.geocode("London").await;
+        assert!(matches!(
+            result,
+            Err(IfoError::ApiError { status: 302, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_geocode_in_box_sends_viewbox() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/search")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "Springfield".into()),
+                mockito::Matcher::UrlEncoded("viewbox".into(), "-90,40,-89,39".into()),
+                mockito::Matcher::UrlEncoded("bounded".into(), "1".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[{"lat": "39.7817", "lon": "-89.6501",
+                     "display_name": "Springfield, Illinois, United States"}]"#,
+            )
+            .create_async()
+            .await;
+
+        let geocoder = Geocoder::builder().base_url(server.url()).build().unwrap();
+        let bbox = BoundingBox::new(39.0, -90.0, 40.0, -89.0).unwrap();
+        let location = geocoder
+            .geocode_in_box("Springfield", bbox, true)
+            .await
+            .unwrap()
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(location.lat, 39.7817);
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_header() {
+        let mut server = mockito::Server::new_async().await;
+        let ua = "my-tracker/0.3 (https://example.com/contact)";
+        let mock = server
+            .mock("GET", "/search")
+            .match_query(mockito::Matcher::Any)
+            .match_header("user-agent", ua)
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"lat": "51.5", "lon": "-0.1", "display_name": "London"}]"#)
+            .create_async()
+            .await;
+
+        let geocoder = Geocoder::builder()
+            .base_url(server.url())
+            .user_agent(ua)
+            .build()
+            .unwrap();
+        geocoder.geocode("London").await.unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_min_importance_filters_poor_match() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/search")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[{"lat": "12.5", "lon": "-1.5", "display_name": "Obscure Hamlet",
+                     "importance": 0.05}]"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+
+        let lenient = Geocoder::builder().base_url(server.url()).build().unwrap();
+        let location = lenient.geocode("Hamlet").await.unwrap().unwrap();
+        assert_eq!(location.importance, Some(0.05));
+
+        let strict = Geocoder::builder()
+            .base_url(server.url())
+            .min_importance(0.2)
+            .build()
+            .unwrap();
+        assert!(strict.geocode("Hamlet").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_geocode_accepts_comma_decimals() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/search")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"lat": "48,8566", "lon": "2,3522", "display_name": "Paris"}]"#)
+            .create_async()
+            .await;
+        let geocoder = Geocoder::builder().base_url(server.url()).build().unwrap();
+
+        let location = geocoder.geocode("Paris").await.unwrap().unwrap();
+        assert_eq!(location.lat, 48.8566);
+        assert_eq!(location.lon, 2.3522);
+    }
+
+    #[test]
+    fn test_parse_degrees() {
+        assert_eq!(parse_degrees("-0.1278").unwrap(), -0.1278);
+        assert_eq!(parse_degrees(" 51,5 ").unwrap(), 51.5);
+        assert!(matches!(
+            parse_degrees("1,234.5"),
+            Err(IfoError::InvalidCoordinates(_))
+        ));
+        assert!(matches!(
+            parse_degrees("north"),
+            Err(IfoError::InvalidCoordinates(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reverse_geocode() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/reverse")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("lat".into(), "51.47".into()),
+                mockito::Matcher::UrlEncoded("lon".into(), "-0.45".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"lat": "51.4706", "lon": "-0.4619", "display_name": "Heathrow Airport",
+                    "class": "aeroway", "type": "aerodrome"}"#,
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/reverse")
+            .match_query(mockito::Matcher::UrlEncoded("lat".into(), "0".into()))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"error": "Unable to geocode"}"#)
+            .create_async()
+            .await;
+        let geocoder = Geocoder::builder().base_url(server.url()).build().unwrap();
+
+        let found = geocoder
+            .reverse(Coordinate::new(51.47, -0.45).unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.display_name, "Heathrow Airport");
+        assert_eq!(found.place_type.as_deref(), Some("aerodrome"));
+
+        let ocean = geocoder
+            .reverse(Coordinate::new(0.0, 0.0).unwrap())
+            .await
+            .unwrap();
+        assert!(ocean.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_photon_geocode_and_reverse() {
+        let body = r#"{"type": "FeatureCollection", "features": [{
+            "type": "Feature",
+            "geometry": {"type": "Point", "coordinates": [13.3888599, 52.5170365]},
+            "properties": {"name": "Berlin", "state": "Berlin", "country": "Germany",
+                           "osm_key": "place", "osm_value": "city"}
+        }]}"#;
+        let mut server = mockito::Server::new_async().await;
+        let search = server
+            .mock("GET", "/api")
+            .match_query(mockito::Matcher::UrlEncoded("q".into(), "Berlin".into()))
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/reverse")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create_async()
+            .await;
+        let geocoder = PhotonGeocoder::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let location = geocoder.geocode("Berlin").await.unwrap().unwrap();
+        search.assert_async().await;
+        assert_eq!(location.lat, 52.5170365);
+        assert_eq!(location.lon, 13.3888599);
+        assert_eq!(location.display_name, "Berlin, Germany");
+        assert_eq!(location.class.as_deref(), Some("place"));
+        assert_eq!(location.place_type.as_deref(), Some("city"));
+        assert_eq!(location.importance, None);
+
+        let reversed = GeocoderBackend::reverse(&geocoder, Coordinate::new(52.5, 13.4).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(reversed.unwrap().display_name, "Berlin, Germany");
+    }
+
+    #[tokio::test]
+    async fn test_photon_no_match() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/api")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type": "FeatureCollection", "features": []}"#)
+            .create_async()
+            .await;
+        let geocoder = PhotonGeocoder::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        assert!(geocoder.geocode("Atlantis").await.unwrap().is_none());
+        assert!(matches!(
+            geocoder.geocode("  ").await,
+            Err(IfoError::EmptyPlaceName)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter_follows_injected_clock() {
+        let clock = governor::clock::FakeRelativeClock::default();
+        let geocoder = Geocoder::builder().clock(clock.clone()).build().unwrap();
+        let real_start = Instant::now();
+
+        geocoder.wait_for_permit().await;
+
+        // The fake clock hasn't moved, so the next permit never comes no
+        // matter how much (paused) Tokio time passes.
+        let second = geocoder.wait_for_permit();
+        tokio::pin!(second);
+        assert!(tokio::time::timeout(Duration::from_secs(5), &mut second)
+            .await
+            .is_err());
+
+        clock.advance(Duration::from_secs(1));
+        tokio::time::timeout(Duration::from_secs(1), second)
+            .await
+            .expect("permit after one second on the fake clock");
+        assert!(real_start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_clones_share_rate_limiter() {
+        let clock = governor::clock::FakeRelativeClock::default();
+        let geocoder = Geocoder::builder().clock(clock).build().unwrap();
+        let clone = geocoder.clone();
+        assert!(Arc::ptr_eq(&geocoder.rate_limiter, &clone.rate_limiter));
+
+        // A permit taken through one clone is gone for the other.
+        assert!(geocoder.rate_limiter.check().is_ok());
+        assert!(clone.rate_limiter.check().is_err());
+    }
+
     #[test]
     fn test_place_validation() {
         // Test would require async runtime
@@ -2,10 +2,101 @@
 //!
 //! A blazing-fast library for querying real-time aircraft data.
 
+#[cfg(feature = "net")]
 pub mod api;
 pub mod error;
+#[cfg(feature = "net")]
 pub mod geocoding;
+pub mod limits;
 pub mod models;
+#[cfg(feature = "net")]
+pub mod output;
+#[cfg(feature = "net")]
+pub mod query;
+pub mod source;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
 
 pub use error::{IfoError, Result};
-pub use models::{Aircraft, BoundingBox, Coordinate, Location};
+pub use models::{Aircraft, BoundingBox, Coordinate, Location, SearchRadius};
+#[cfg(feature = "net")]
+pub use query::{plan_query, run_batch, run_query, with_deadline, Query, QueryInput, QueryResult};
+pub use source::{AircraftSource, DynAircraftSource, MaybeSend};
+
+/// Aircraft within `radius_km` of a place name or `"lat,lon"` string, using
+/// the public OpenSky and Nominatim services with default settings.
+///
+/// Input that parses as coordinates is used directly; anything else is
+/// geocoded first. For control over timeouts, endpoints or rate limits, use
+/// [`query::run_query_with`] with your own clients.
+#[cfg(feature = "net")]
+pub async fn aircraft_over(place_or_coords: &str, radius_km: f64) -> Result<Vec<Aircraft>> {
+    let client = api::OpenSkyClient::new(api::DEFAULT_TIMEOUT_SECS)?;
+    let geocoder = geocoding::Geocoder::new(api::DEFAULT_TIMEOUT_SECS)?;
+    aircraft_over_with(&client, &geocoder, place_or_coords, radius_km).await
+}
+
+#[cfg(feature = "net")]
+async fn aircraft_over_with(
+    client: &api::OpenSkyClient,
+    geocoder: &impl geocoding::GeocoderBackend,
+    place_or_coords: &str,
+    radius_km: f64,
+) -> Result<Vec<Aircraft>> {
+    let input = QueryInput::detect(place_or_coords);
+    let radius = SearchRadius::Kilometers(radius_km);
+    Ok(query::run_query_with(client, geocoder, &input, radius)
+        .await?
+        .aircraft)
+}
+
+#[cfg(all(test, feature = "net"))]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+
+    #[tokio::test]
+    async fn test_aircraft_over_place_and_coords() {
+        let mut server = Server::new_async().await;
+        let search = server
+            .mock("GET", "/search")
+            .match_query(Matcher::UrlEncoded("q".into(), "Reykjavik".into()))
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"lat": "64.1466", "lon": "-21.9426", "display_name": "Reykjavik"}]"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let states = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"time": 1700000000, "states": [
+                    ["4cc2a1", "ICE123  ", "Iceland", null, null, -21.9, 64.1, 900.0, false, null, null, null, null, null, null, false, 0]
+                ]}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let client = api::OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let geocoder = geocoding::Geocoder::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let by_place = aircraft_over_with(&client, &geocoder, "Reykjavik", 25.0)
+            .await
+            .unwrap();
+        let by_coords = aircraft_over_with(&client, &geocoder, "64.1,-21.9", 25.0)
+            .await
+            .unwrap();
+
+        search.assert_async().await;
+        states.assert_async().await;
+        assert_eq!(by_place.len(), 1);
+        assert_eq!(by_coords[0].airline(), Some("Icelandair"));
+    }
+}
@@ -0,0 +1,90 @@
+//! Documented OpenSky Network API limits.
+//!
+//! Values follow the OpenSky REST API documentation. Areas are measured in
+//! square degrees (latitude span times longitude span), which is how OpenSky
+//! bills `/states/all` requests.
+
+/// Daily API credits for anonymous (unauthenticated) users.
+pub const ANONYMOUS_DAILY_CREDITS: u32 = 400;
+
+/// Daily API credits for authenticated users.
+pub const AUTHENTICATED_DAILY_CREDITS: u32 = 4_000;
+
+/// Daily API credits for authenticated users who also feed ADS-B data.
+pub const FEEDER_DAILY_CREDITS: u32 = 8_000;
+
+/// Largest area billed at one credit per `/states/all` request.
+pub const ONE_CREDIT_AREA_SQ_DEG: f64 = 25.0;
+
+/// Largest area billed at two credits per `/states/all` request.
+pub const TWO_CREDIT_AREA_SQ_DEG: f64 = 100.0;
+
+/// Largest area billed at three credits; anything bigger costs four.
+pub const THREE_CREDIT_AREA_SQ_DEG: f64 = 400.0;
+
+/// Most tiles one tiled query may be split into.
+///
+/// Every tile costs at least one credit, so a split into more tiles than the
+/// largest daily budget could never finish.
+pub const MAX_TILES_PER_QUERY: usize = FEEDER_DAILY_CREDITS as usize;
+
+/// Time resolution of state vectors for anonymous users, in seconds.
+pub const ANONYMOUS_TIME_RESOLUTION_SECS: u64 = 10;
+
+/// Time resolution of state vectors for authenticated users, in seconds.
+pub const AUTHENTICATED_TIME_RESOLUTION_SECS: u64 = 5;
+
+/// How far back authenticated users may request state vectors, in seconds.
+/// Anonymous users only ever get the most recent snapshot.
+pub const AUTHENTICATED_STATE_HISTORY_SECS: u64 = 3_600;
+
+/// Longest interval accepted by `/flights/all`, in seconds (2 hours).
+pub const FLIGHTS_ALL_MAX_INTERVAL_SECS: u64 = 2 * 3_600;
+
+/// Longest interval accepted by `/flights/aircraft`, in seconds (2 days).
+pub const FLIGHTS_BY_AIRCRAFT_MAX_INTERVAL_SECS: u64 = 2 * 86_400;
+
+/// Longest interval accepted by `/flights/arrival` and `/flights/departure`,
+/// in seconds (7 days).
+pub const FLIGHTS_BY_AIRPORT_MAX_INTERVAL_SECS: u64 = 7 * 86_400;
+
+/// Largest area, in square degrees, worth sending as a single request.
+///
+/// Anonymous users stay in the one-credit tier so the small daily budget
+/// lasts; authenticated users may go up to the largest tier below a
+/// global query.
+pub fn max_area_for(authenticated: bool) -> f64 {
+    if authenticated {
+        THREE_CREDIT_AREA_SQ_DEG
+    } else {
+        ONE_CREDIT_AREA_SQ_DEG
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_documented_values() {
+        assert_eq!(ANONYMOUS_DAILY_CREDITS, 400);
+        assert_eq!(AUTHENTICATED_DAILY_CREDITS, 4_000);
+        assert_eq!(FEEDER_DAILY_CREDITS, 8_000);
+        assert_eq!(ONE_CREDIT_AREA_SQ_DEG, 25.0);
+        assert_eq!(TWO_CREDIT_AREA_SQ_DEG, 100.0);
+        assert_eq!(THREE_CREDIT_AREA_SQ_DEG, 400.0);
+        assert_eq!(MAX_TILES_PER_QUERY, 8_000);
+        assert_eq!(ANONYMOUS_TIME_RESOLUTION_SECS, 10);
+        assert_eq!(AUTHENTICATED_TIME_RESOLUTION_SECS, 5);
+        assert_eq!(AUTHENTICATED_STATE_HISTORY_SECS, 3_600);
+        assert_eq!(FLIGHTS_ALL_MAX_INTERVAL_SECS, 7_200);
+        assert_eq!(FLIGHTS_BY_AIRCRAFT_MAX_INTERVAL_SECS, 172_800);
+        assert_eq!(FLIGHTS_BY_AIRPORT_MAX_INTERVAL_SECS, 604_800);
+    }
+
+    #[test]
+    fn test_max_area_for() {
+        assert_eq!(max_area_for(false), 25.0);
+        assert_eq!(max_area_for(true), 400.0);
+    }
+}
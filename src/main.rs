@@ -2,41 +2,282 @@
 //!
 //! Query aircraft flying over a location using coordinates or place name.
 
-use clap::{Args, Parser};
+// The CLI needs a terminal and Tokio's full runtime, neither of which
+// exists in the browser; on wasm32 only the library is usable, and this
+// binary is empty so that `cargo test --target wasm32-unknown-unknown` can
+// still build it alongside the integration tests.
+#![cfg_attr(target_arch = "wasm32", no_main)]
+#![cfg(not(target_arch = "wasm32"))]
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use reqwest::Url;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
 use std::process;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+#[cfg(feature = "html")]
+use ifo::output::HtmlFormat;
+#[cfg(feature = "msgpack")]
+use ifo::output::MsgpackFormat;
 use ifo::{
     api::OpenSkyClient,
-    geocoding::Geocoder,
-    models::{BoundingBox, Coordinate},
-    Result,
+    geocoding::{Geocoder, PhotonGeocoder},
+    models::{
+        self, Aircraft, BoundingBox, Coordinate, DistanceUnit, FlightInfo, GroundFilter, Location,
+        SearchRadius,
+    },
+    output::{CsvFormat, JsonFormat, NdjsonFormat, OutputFormat, Template, TextFormat},
+    query::{plan_query_with, run_query_with},
+    with_deadline, IfoError, QueryInput, QueryResult, Result,
 };
 
 #[derive(Parser)]
 #[command(name = "ifo")]
 #[command(author, version, about = "Identified Flying Object: Query aircraft flying over a location", long_about = None)]
 #[command(after_help = r#"EXAMPLES:
+    ifo "London"                         # Place name or coordinates, detected
     ifo --coords "37.7,-122.4"          # Coordinates
     ifo --place "San Francisco"          # Place name
     ifo --place "London, UK"             # Place with country
-    ifo --coords "40.7,-74.0" --radius 1.0  # Custom radius"#)]
+    ifo --coords "40.7,-74.0" --radius 1.0  # Custom radius
+    ifo --place "Paris" --radius 50 --radius-unit km  # Radius in km
+    ifo flights --icao24 3c6444 --since "1d ago"       # Recent flights of one aircraft"#)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Location input (coordinates or place name)
     #[command(flatten)]
     location: LocationArgs,
 
-    /// Search radius in degrees (default: 0.5, approximately 55km)
+    /// Search radius, in the unit given by --radius-unit (default: 0.5 degrees, approximately 55km)
     #[arg(long, default_value = "0.5")]
     radius: f64,
 
+    /// Unit of --radius
+    #[arg(long, value_enum, default_value = "deg")]
+    radius_unit: RadiusUnit,
+
+    /// Unit for distances from the search center in text output
+    #[arg(long, value_enum, default_value = "km")]
+    distance_unit: DistanceUnitArg,
+
+    /// Decimal places for distances in text output
+    #[arg(long, value_name = "DIGITS", default_value = "1")]
+    distance_precision: usize,
+
     /// API request timeout in seconds (default: 10)
     #[arg(long, default_value = "10")]
     timeout: u64,
+
+    /// Overall time limit in seconds for the whole query, including geocoding
+    #[arg(long, value_name = "SECS")]
+    deadline: Option<u64>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    format: FormatArg,
+
+    /// Only include these comma-separated fields in JSON and CSV output,
+    /// e.g. "icao24,callsign,baro_altitude" (names as in the CSV header)
+    #[arg(long, value_delimiter = ',', value_name = "FIELDS", value_parser = parse_field)]
+    fields: Vec<String>,
+
+    /// Also write the results in FORMAT to PATH; repeat for several files,
+    /// e.g. "--output json=out.json --output csv=out.csv" ("-" is stdout)
+    #[arg(long = "output", value_name = "FORMAT=PATH", value_parser = parse_output_target)]
+    outputs: Vec<OutputTarget>,
+
+    /// Print one line per aircraft from this template instead of the text
+    /// listing, e.g. "{callsign} {altitude_ft}ft {heading}°"; see --help
+    /// for the placeholder names
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = Template::parse,
+        conflicts_with_all = ["format", "group_by"],
+        long_help = TEMPLATE_HELP
+    )]
+    template: Option<Template>,
+
+    /// Geocoding service used to look up place names
+    #[arg(long, value_enum, default_value = "nominatim")]
+    geocoder: GeocoderArg,
+
+    /// Print results in sections grouped by the given field
+    #[arg(long, value_enum, value_name = "FIELD")]
+    group_by: Option<GroupBy>,
+
+    /// Order aircraft by this field; aircraft without it come last
+    #[arg(long, value_enum, value_name = "FIELD")]
+    sort: Option<SortKey>,
+
+    /// Aircraft on the ground to show
+    #[arg(long, value_enum, default_value = "include")]
+    ground: GroundArg,
+
+    /// Only show aircraft at or above this flight level (e.g. 100 for FL100,
+    /// 10,000 ft pressure altitude)
+    #[arg(long, value_name = "FL")]
+    min_fl: Option<u32>,
+
+    /// Only show aircraft at or below this flight level
+    #[arg(long, value_name = "FL")]
+    max_fl: Option<u32>,
+
+    /// Only show aircraft squawking an emergency code (7500, 7600 or 7700)
+    #[arg(long)]
+    emergencies_only: bool,
+
+    /// Print only the number of aircraft found (`{"count": N}` with --format json)
+    #[arg(long, conflicts_with_all = ["template", "group_by", "fields", "dry_run"])]
+    count: bool,
+
+    /// Print the request that would be sent, then exit without sending it
+    #[arg(long, conflicts_with = "analyze")]
+    dry_run: bool,
+
+    /// Print how long the query took after the results, and warn about
+    /// aircraft reporting implausible data
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// User-Agent sent to OpenSky and the geocoder; for heavy use, include a
+    /// contact email or URL as Nominatim's usage policy asks
+    #[arg(long, global = true, env = "IFO_USER_AGENT", value_name = "STRING")]
+    user_agent: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the recorded flights of one aircraft in a time window
+    Flights(FlightsArgs),
+}
+
+#[derive(Args)]
+struct FlightsArgs {
+    /// ICAO24 transponder address (e.g., "3c6444")
+    #[arg(long, value_name = "HEX", value_parser = models::normalize_icao24)]
+    icao24: String,
+
+    /// Start of the window: RFC 3339 UTC ("2025-11-01T08:00:00Z") or relative ("2h ago")
+    #[arg(long, value_name = "TIME")]
+    since: String,
+
+    /// End of the window, in the same forms as --since
+    #[arg(long, value_name = "TIME", default_value = "now")]
+    until: String,
+
+    /// API request timeout in seconds (default: 10)
+    #[arg(long, default_value = "10")]
+    timeout: u64,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    format: FlightsFormatArg,
+}
+
+/// The `--format` choices of `flights`: CSV and HTML describe aircraft
+/// positions and have no flight equivalent.
+#[derive(Clone, Copy, ValueEnum)]
+enum FlightsFormatArg {
+    /// Human-readable table
+    Text,
+    /// JSON array of flights
+    Json,
+    /// MessagePack array of flights (binary)
+    #[cfg(feature = "msgpack")]
+    Msgpack,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum RadiusUnit {
+    /// Degrees of latitude/longitude
+    Deg,
+    /// Kilometres
+    Km,
+    /// Nautical miles
+    Nm,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GeocoderArg {
+    /// Nominatim (nominatim.openstreetmap.org), limited to 1 request/s
+    Nominatim,
+    /// Photon (photon.komoot.io), more forgiving of typos and partial names
+    Photon,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GroundArg {
+    /// Show only aircraft on the ground
+    Only,
+    /// Hide aircraft on the ground
+    Exclude,
+    /// Show both airborne and ground aircraft
+    Include,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DistanceUnitArg {
+    /// Kilometres
+    Km,
+    /// Metres
+    M,
+    /// Nautical miles
+    Nm,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FormatArg {
+    /// Human-readable listing
+    Text,
+    /// JSON array of aircraft
+    Json,
+    /// One JSON object per aircraft per line, as read back by --analyze
+    Ndjson,
+    /// Comma-separated values with a header row
+    Csv,
+    /// MessagePack array of aircraft (binary)
+    #[cfg(feature = "msgpack")]
+    Msgpack,
+    /// Self-contained HTML page with the aircraft on a map
+    #[cfg(feature = "html")]
+    Html,
+}
+
+/// One `--output` pair: where to write an extra copy of the results.
+#[derive(Clone)]
+struct OutputTarget {
+    format: FormatArg,
+    path: String,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GroupBy {
+    /// Group by origin country, sorted alphabetically
+    Country,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SortKey {
+    /// ICAO24 address
+    Icao24,
+    /// Callsign, alphabetically
+    Callsign,
+    /// Barometric altitude, lowest first
+    Altitude,
 }
 
 #[derive(Args)]
 #[group(required = true, multiple = false)]
 struct LocationArgs {
+    /// Coordinates ("51.5,-0.1") or a place name ("London"), detected automatically
+    #[arg(value_name = "LOCATION")]
+    location: Option<String>,
+
     /// Location coordinates in format "latitude,longitude" (e.g., "37.7,-122.4")
     #[arg(long, value_name = "LAT,LON")]
     coords: Option<String>,
@@ -44,26 +285,153 @@ struct LocationArgs {
     /// Place name (e.g., "San Francisco" or "London, UK")
     #[arg(long, value_name = "NAME")]
     place: Option<String>,
+
+    /// Exact search area "lat_min,lon_min,lat_max,lon_max"; --radius is ignored
+    #[arg(
+        long,
+        value_name = "LAT_MIN,LON_MIN,LAT_MAX,LON_MAX",
+        value_parser = BoundingBox::parse,
+        allow_hyphen_values = true
+    )]
+    bbox: Option<BoundingBox>,
+
+    /// Analyze aircraft captured with --format ndjson, from a file ("-" for stdin), instead of querying
+    #[arg(long, value_name = "PATH")]
+    analyze: Option<String>,
 }
 
-/// Parse coordinate string in format 'lat,lon'.
-fn parse_coordinates(coord_str: &str) -> Result<Coordinate> {
-    let parts: Vec<&str> = coord_str.split(',').collect();
-    if parts.len() != 2 {
-        return Err(ifo::IfoError::InvalidCoordinates(
-            "Coordinates must be in format 'latitude,longitude'".to_string(),
-        ));
+/// Turn the location arguments into a query input.
+///
+/// A `--place` value that is really a coordinate pair is used directly
+/// rather than being sent to the geocoder.
+fn resolve_input(location: &LocationArgs) -> Result<QueryInput> {
+    if let Some(coords_str) = &location.coords {
+        Ok(QueryInput::Coords(Coordinate::parse(coords_str)?))
+    } else if let Some(bbox) = location.bbox {
+        Ok(QueryInput::Area(bbox))
+    } else if let Some(location) = &location.location {
+        Ok(QueryInput::detect(location))
+    } else if let Some(place) = &location.place {
+        match Coordinate::parse(place) {
+            Ok(coord) => {
+                eprintln!("Note: --place value looks like coordinates; skipping geocoding");
+                Ok(QueryInput::Coords(coord))
+            }
+            Err(_) => Ok(QueryInput::Place(place.clone())),
+        }
+    } else {
+        unreachable!("Clap ensures one location arg is provided");
+    }
+}
+
+/// Run the query with the geocoding service chosen on the command line.
+async fn query(input: &QueryInput, radius: SearchRadius, cli: &Cli) -> Result<QueryResult> {
+    let client = opensky_client(cli.timeout, cli.user_agent.as_deref())?;
+    match cli.geocoder {
+        GeocoderArg::Nominatim => {
+            let geocoder = nominatim(cli)?;
+            run_query_with(&client, &geocoder, input, radius).await
+        }
+        GeocoderArg::Photon => {
+            let geocoder = photon(cli)?;
+            run_query_with(&client, &geocoder, input, radius).await
+        }
+    }
+}
+
+/// The first request [`query`] would send.
+fn plan(input: &QueryInput, radius: SearchRadius, cli: &Cli) -> Result<Url> {
+    let client = opensky_client(cli.timeout, cli.user_agent.as_deref())?;
+    match cli.geocoder {
+        GeocoderArg::Nominatim => plan_query_with(&client, &nominatim(cli)?, input, radius),
+        GeocoderArg::Photon => plan_query_with(&client, &photon(cli)?, input, radius),
+    }
+}
+
+fn opensky_client(timeout: u64, user_agent: Option<&str>) -> Result<OpenSkyClient> {
+    let mut builder = OpenSkyClient::builder().timeout(Duration::from_secs(timeout));
+    if let Some(user_agent) = user_agent {
+        builder = builder.user_agent(user_agent);
+    }
+    builder.build()
+}
+
+fn nominatim(cli: &Cli) -> Result<Geocoder> {
+    let mut builder = Geocoder::builder().timeout(Duration::from_secs(cli.timeout));
+    if let Some(user_agent) = &cli.user_agent {
+        builder = builder.user_agent(user_agent);
+    }
+    builder.build()
+}
+
+fn photon(cli: &Cli) -> Result<PhotonGeocoder> {
+    let mut builder = PhotonGeocoder::builder().timeout(Duration::from_secs(cli.timeout));
+    if let Some(user_agent) = &cli.user_agent {
+        builder = builder.user_agent(user_agent);
     }
+    builder.build()
+}
 
-    let lat = parts[0].trim().parse::<f64>().map_err(|_| {
-        ifo::IfoError::InvalidCoordinates(format!("Invalid latitude: {}", parts[0]))
-    })?;
+fn search_radius(radius: f64, unit: RadiusUnit) -> SearchRadius {
+    match unit {
+        RadiusUnit::Deg => SearchRadius::Degrees(radius),
+        RadiusUnit::Km => SearchRadius::Kilometers(radius),
+        RadiusUnit::Nm => SearchRadius::NauticalMiles(radius),
+    }
+}
 
-    let lon = parts[1].trim().parse::<f64>().map_err(|_| {
-        ifo::IfoError::InvalidCoordinates(format!("Invalid longitude: {}", parts[1]))
-    })?;
+/// Load captured aircraft for offline analysis from a file, or stdin for "-".
+fn read_capture(path: &str) -> Result<QueryResult> {
+    if path == "-" {
+        capture_from(io::stdin().lock(), "stdin")
+    } else {
+        capture_from(BufReader::new(File::open(path)?), path)
+    }
+}
 
-    Coordinate::new(lat, lon)
+/// Captured NDJSON aircraft as a query result named `name`.
+fn capture_from(reader: impl BufRead, name: &str) -> Result<QueryResult> {
+    let started = Instant::now();
+    let aircraft = models::read_ndjson(reader)?;
+    Ok(QueryResult {
+        center: None,
+        location_name: name.to_string(),
+        aircraft,
+        snapshot_time: None,
+        elapsed: started.elapsed(),
+        location: None,
+    })
+}
+
+/// Apply the filter and sort options to the aircraft of `result`.
+fn refine(result: &mut QueryResult, cli: &Cli) {
+    let ground = match cli.ground {
+        GroundArg::Only => GroundFilter::OnlyGround,
+        GroundArg::Exclude => GroundFilter::OnlyAirborne,
+        GroundArg::Include => GroundFilter::Both,
+    };
+    let aircraft = std::mem::take(&mut result.aircraft);
+    let aircraft = models::filter_ground(aircraft, ground);
+    let mut aircraft = models::filter_flight_levels(aircraft, cli.min_fl, cli.max_fl);
+    if cli.emergencies_only {
+        aircraft = models::only_emergencies(aircraft);
+    }
+
+    match cli.sort {
+        Some(SortKey::Icao24) => aircraft.sort_by(|a, b| a.icao24.cmp(&b.icao24)),
+        Some(SortKey::Callsign) => aircraft.sort_by_key(|ac| {
+            let callsign = ac.callsign.as_deref().map(str::trim);
+            (callsign.is_none(), callsign.map(str::to_string))
+        }),
+        Some(SortKey::Altitude) => {
+            aircraft.sort_by(|a, b| match (a.baro_altitude, b.baro_altitude) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            })
+        }
+        None => {}
+    }
+    result.aircraft = aircraft;
 }
 
 #[tokio::main]
@@ -77,72 +445,710 @@ async fn main() {
 async fn run() -> Result<()> {
     let cli = Cli::parse();
 
-    // Get coordinates (either from direct coords or geocoding)
-    let (coord, location_name) = if let Some(coords_str) = &cli.location.coords {
-        let coord = parse_coordinates(coords_str)?;
-        (coord, format!("{},{}", coord.latitude, coord.longitude))
-    } else if let Some(place) = &cli.location.place {
-        // Geocode place name
-        let geocoder = Geocoder::new(cli.timeout)?;
-        match geocoder.geocode(place).await? {
-            Some(location) => {
-                println!(
-                    "Found location: {} ({:.4}, {:.4})",
-                    location.display_name, location.lat, location.lon
-                );
-                let coord = Coordinate::new(location.lat, location.lon)?;
-                (coord, location.display_name)
+    if let Some(Command::Flights(args)) = &cli.command {
+        return run_flights(args, cli.user_agent.as_deref()).await;
+    }
+
+    let mut result = if let Some(path) = &cli.location.analyze {
+        read_capture(path)?
+    } else {
+        let input = resolve_input(&cli.location)?;
+        let radius = search_radius(cli.radius, cli.radius_unit);
+        if cli.dry_run {
+            println!("GET {}", plan(&input, radius, &cli)?);
+            if let QueryInput::Place(_) = input {
+                eprintln!("Note: the OpenSky request depends on the geocoding result");
             }
-            None => {
-                return Err(ifo::IfoError::LocationNotFound(place.clone()));
+            return Ok(());
+        }
+        let query = query(&input, radius, &cli);
+        match cli.deadline {
+            Some(secs) => with_deadline(Duration::from_secs(secs), query).await?,
+            None => query.await?,
+        }
+    };
+
+    if let Some(note) = result.location.as_ref().and_then(water_advisory) {
+        eprintln!("Note: {}", note);
+    }
+
+    refine(&mut result, &cli);
+
+    if cli.verbose {
+        for ac in &result.aircraft {
+            for anomaly in ac.sanity_check() {
+                eprintln!("Warning: {}: {}", ac.icao24, anomaly);
             }
         }
+    }
+
+    let elapsed = result.elapsed;
+
+    #[cfg(feature = "msgpack")]
+    if matches!(cli.format, FormatArg::Msgpack) && !cli.count {
+        use std::io::IsTerminal;
+
+        if io::stdout().is_terminal() {
+            eprintln!("Warning: writing binary MessagePack to a terminal; redirect to a file");
+        }
+    }
+    write_results(&mut io::stdout().lock(), &result, &cli)?;
+
+    if cli.verbose {
+        // stderr keeps machine-readable output on stdout intact
+        eprintln!("Completed in {:.2}s", elapsed.as_secs_f64());
+    }
+
+    Ok(())
+}
+
+/// Write `result` to `out` as --count or --format ask, then a copy to
+/// each --output target ("-" being `out` as well).
+fn write_results(out: &mut impl Write, result: &QueryResult, cli: &Cli) -> Result<()> {
+    if cli.count {
+        write_count(out, result.aircraft.len(), cli.format)?;
     } else {
-        unreachable!("Clap ensures one location arg is provided");
+        formatter(cli.format, cli).write(out, result)?;
+    }
+    for target in &cli.outputs {
+        let format = formatter(target.format, cli);
+        if target.path == "-" {
+            format.write(out, result)?;
+        } else {
+            let mut file = io::BufWriter::new(File::create(&target.path)?);
+            format.write(&mut file, result)?;
+            file.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Print just the aircraft count: a bare number, or `{"count": N}` for JSON.
+fn write_count(out: &mut impl Write, count: usize, format: FormatArg) -> Result<()> {
+    match format {
+        FormatArg::Json | FormatArg::Ndjson => {
+            writeln!(out, "{}", serde_json::json!({ "count": count }))?
+        }
+        _ => writeln!(out, "{}", count)?,
+    }
+    Ok(())
+}
+
+/// The formatter for `format`, configured by the options that go with it.
+fn formatter(format: FormatArg, cli: &Cli) -> Box<dyn OutputFormat> {
+    match format {
+        FormatArg::Text => match &cli.template {
+            Some(template) => Box::new(template.clone()),
+            None => Box::new(text_format(cli)),
+        },
+        FormatArg::Json => Box::new(JsonFormat {
+            fields: cli.fields.clone(),
+        }),
+        FormatArg::Ndjson => Box::new(NdjsonFormat {
+            fields: cli.fields.clone(),
+        }),
+        FormatArg::Csv => Box::new(CsvFormat {
+            fields: cli.fields.clone(),
+        }),
+        #[cfg(feature = "msgpack")]
+        FormatArg::Msgpack => Box::new(MsgpackFormat),
+        #[cfg(feature = "html")]
+        FormatArg::Html => Box::new(HtmlFormat),
+    }
+}
+
+fn text_format(cli: &Cli) -> TextFormat {
+    let distance_unit = match cli.distance_unit {
+        DistanceUnitArg::Km => DistanceUnit::Kilometers,
+        DistanceUnitArg::M => DistanceUnit::Meters,
+        DistanceUnitArg::Nm => DistanceUnit::NauticalMiles,
+    };
+    TextFormat {
+        group_by_country: matches!(cli.group_by, Some(GroupBy::Country)),
+        distance_unit,
+        distance_precision: cli.distance_precision,
+    }
+}
+
+/// Explain an empty-looking result when a place name resolved to open water.
+fn water_advisory(location: &Location) -> Option<String> {
+    location.is_water().then(|| {
+        format!(
+            "{} is a body of water; OpenSky receivers are on land, so few aircraft may show up",
+            location.display_name
+        )
+    })
+}
+
+/// Parse a `--since`/`--until` value into Unix seconds.
+///
+/// Accepts `now`, a duration followed by `ago` (anything humantime
+/// understands, e.g. `90m ago` or `1day 2h ago`) or an RFC 3339 UTC
+/// timestamp.
+fn parse_time(value: &str, now: SystemTime) -> Result<i64> {
+    let value = value.trim();
+    let invalid = |e: &dyn std::fmt::Display| {
+        IfoError::InvalidTimeWindow(format!("cannot parse time {:?}: {}", value, e))
     };
 
-    // Create bounding box
-    let bbox = BoundingBox::from_center(coord, cli.radius);
+    let time = if value.eq_ignore_ascii_case("now") {
+        now
+    } else if let Some(ago) = value.strip_suffix("ago") {
+        let offset = humantime::parse_duration(ago.trim()).map_err(|e| invalid(&e))?;
+        now.checked_sub(offset)
+            .ok_or_else(|| invalid(&"before the Unix epoch"))?
+    } else {
+        humantime::parse_rfc3339_weak(value).map_err(|e| invalid(&e))?
+    };
 
-    // Query API
-    let api = OpenSkyClient::new(cli.timeout)?;
-    let aircraft = api.get_aircraft_in_area(bbox).await?;
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| invalid(&"before the Unix epoch"))?
+        .as_secs();
+    Ok(secs as i64)
+}
 
-    // Display results
-    if aircraft.is_empty() {
-        println!("No aircraft found near {}", location_name);
-        return Ok(());
+async fn run_flights(args: &FlightsArgs, user_agent: Option<&str>) -> Result<()> {
+    let now = SystemTime::now();
+    let begin = parse_time(&args.since, now)?;
+    let end = parse_time(&args.until, now)?;
+
+    let client = opensky_client(args.timeout, user_agent)?;
+    let flights = client
+        .get_flights_by_aircraft(&args.icao24, begin, end)
+        .await?;
+
+    match args.format {
+        FlightsFormatArg::Text => print_flights(&args.icao24, &flights),
+        FlightsFormatArg::Json => {
+            let mut stdout = io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &flights)?;
+            writeln!(stdout)?;
+        }
+        #[cfg(feature = "msgpack")]
+        FlightsFormatArg::Msgpack => {
+            let bytes = rmp_serde::to_vec_named(&flights)?;
+            io::stdout().lock().write_all(&bytes)?;
+        }
     }
+    Ok(())
+}
 
+fn print_flights(icao24: &str, flights: &[FlightInfo]) {
+    if flights.is_empty() {
+        println!("No flights found for {}", icao24);
+        return;
+    }
+
+    println!("Found {} flights for {}:\n", flights.len(), icao24);
     println!(
-        "Found {} aircraft near {}:\n",
-        aircraft.len(),
-        location_name
+        "{:<8}  {:<4}  {:<4}  {:<20}  {:<20}",
+        "CALLSIGN", "FROM", "TO", "FIRST SEEN", "LAST SEEN"
     );
+    for flight in flights {
+        println!(
+            "{:<8}  {:<4}  {:<4}  {:<20}  {:<20}",
+            flight.callsign.as_deref().unwrap_or("N/A"),
+            flight.est_departure_airport.as_deref().unwrap_or("----"),
+            flight.est_arrival_airport.as_deref().unwrap_or("----"),
+            format_timestamp(flight.first_seen),
+            format_timestamp(flight.last_seen),
+        );
+    }
+}
 
-    for ac in aircraft {
-        println!("Callsign: {}", ac.callsign.as_deref().unwrap_or("N/A"));
-        println!("  ICAO24: {}", ac.icao24);
-        println!("  Country: {}", ac.origin_country);
+/// Format Unix seconds as RFC 3339 UTC.
+fn format_timestamp(secs: i64) -> String {
+    models::format_unix_time(secs).unwrap_or_else(|| "N/A".to_string())
+}
 
-        if let (Some(lat), Some(lon)) = (ac.latitude, ac.longitude) {
-            println!("  Position: {:.4}, {:.4}", lat, lon);
-        }
+/// Accept a `--fields` entry if it names an [`Aircraft`] column.
+fn parse_field(name: &str) -> Result<String> {
+    let name = name.trim();
+    if Aircraft::header().contains(&name) {
+        Ok(name.to_string())
+    } else {
+        Err(IfoError::UnknownField(name.to_string()))
+    }
+}
 
-        if let Some(alt) = ac.baro_altitude {
-            println!("  Altitude: {:.0} m", alt);
-        }
+/// Parse a `--output` value such as `json=out.json`.
+fn parse_output_target(value: &str) -> std::result::Result<OutputTarget, String> {
+    let (name, path) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected FORMAT=PATH, got {:?}", value))?;
+    let format = FormatArg::from_str(name.trim(), true)?;
+    if path.is_empty() {
+        return Err(format!("missing path for {} output", name.trim()));
+    }
+    Ok(OutputTarget {
+        format,
+        path: path.to_string(),
+    })
+}
 
-        if let Some(vel) = ac.velocity {
-            println!("  Velocity: {:.1} m/s", vel);
-        }
+const TEMPLATE_HELP: &str = "\
+Print one line per aircraft from this template instead of the text listing,
+e.g. \"{callsign} {altitude_ft}ft {heading}°\". Placeholders: icao24, callsign,
+country, airline, squawk, latitude, longitude, altitude (m), altitude_ft,
+velocity (m/s), speed_kt, heading (degrees), vertical_rate (m/s), trend,
+distance_km and on_ground. Unknown values print as N/A; write {{ and }} for
+literal braces.";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_place_that_is_coordinates_skips_geocoding() {
+        let cli = Cli::try_parse_from(["ifo", "--place", "37.7,-122.4"]).unwrap();
+        let input = resolve_input(&cli.location).unwrap();
+        assert_eq!(
+            input,
+            QueryInput::Coords(Coordinate::new(37.7, -122.4).unwrap())
+        );
+
+        let cli = Cli::try_parse_from(["ifo", "--place", "London, UK"]).unwrap();
+        let input = resolve_input(&cli.location).unwrap();
+        assert_eq!(input, QueryInput::Place("London, UK".to_string()));
+    }
+
+    #[test]
+    fn test_positional_location_detects_kind() {
+        let cli = Cli::try_parse_from(["ifo", "51.5,-0.1"]).unwrap();
+        assert_eq!(
+            resolve_input(&cli.location).unwrap(),
+            QueryInput::Coords(Coordinate::new(51.5, -0.1).unwrap())
+        );
+
+        let cli = Cli::try_parse_from(["ifo", "London"]).unwrap();
+        assert_eq!(
+            resolve_input(&cli.location).unwrap(),
+            QueryInput::Place("London".to_string())
+        );
+
+        assert!(Cli::try_parse_from(["ifo", "London", "--coords", "51.5,-0.1"]).is_err());
+    }
+
+    #[test]
+    fn test_geocoder_choice() {
+        let cli = Cli::try_parse_from(["ifo", "--place", "Berlin"]).unwrap();
+        assert!(matches!(cli.geocoder, GeocoderArg::Nominatim));
+
+        let cli =
+            Cli::try_parse_from(["ifo", "--place", "Berlin", "--geocoder", "photon"]).unwrap();
+        let url = plan(
+            &QueryInput::Place("Berlin".to_string()),
+            SearchRadius::Degrees(0.5),
+            &cli,
+        )
+        .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://photon.komoot.io/api?q=Berlin&limit=1"
+        );
+    }
 
-        if ac.on_ground {
-            println!("  Status: On ground");
+    #[test]
+    fn test_bbox_argument() {
+        let cli = Cli::try_parse_from(["ifo", "--bbox", "-34.1,150.9,-33.7,151.4"]).unwrap();
+        assert_eq!(
+            resolve_input(&cli.location).unwrap(),
+            QueryInput::Area(BoundingBox::new(-34.1, 150.9, -33.7, 151.4).unwrap())
+        );
+
+        assert!(Cli::try_parse_from(["ifo", "--bbox", "-33.7,150.9,-34.1,151.4"]).is_err());
+        assert!(Cli::try_parse_from(["ifo", "--bbox", "0,0,1,1", "--place", "Sydney"]).is_err());
+    }
+
+    #[test]
+    fn test_water_advisory() {
+        let mut location = Location {
+            lat: 0.0,
+            lon: -160.0,
+            display_name: "Pacific Ocean".to_string(),
+            class: Some("place".to_string()),
+            place_type: Some("ocean".to_string()),
+            importance: None,
+        };
+        let note = water_advisory(&location).unwrap();
+        assert!(note.starts_with("Pacific Ocean is a body of water"));
+
+        location.class = Some("boundary".to_string());
+        location.place_type = Some("administrative".to_string());
+        assert_eq!(water_advisory(&location), None);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        assert_eq!(parse_time("now", now).unwrap(), 1_700_000_000);
+        assert_eq!(parse_time("2h ago", now).unwrap(), 1_700_000_000 - 7200);
+        assert_eq!(
+            parse_time("1day 30m ago", now).unwrap(),
+            1_700_000_000 - 88_200
+        );
+        assert_eq!(
+            parse_time("2023-11-14T22:13:20Z", now).unwrap(),
+            1_700_000_000
+        );
+        assert!(matches!(
+            parse_time("yesterday-ish", now),
+            Err(IfoError::InvalidTimeWindow(_))
+        ));
+    }
+
+    #[test]
+    fn test_fields_restrict_json_and_csv() {
+        let cli = Cli::try_parse_from([
+            "ifo",
+            "--coords",
+            "0,0",
+            "--format",
+            "csv",
+            "--fields",
+            "callsign, baro_altitude",
+        ])
+        .unwrap();
+        assert_eq!(cli.fields, ["callsign", "baro_altitude"]);
+
+        let result = QueryResult {
+            center: None,
+            location_name: "capture".to_string(),
+            aircraft: vec![Aircraft::builder("abc123")
+                .callsign("UAL,123")
+                .origin_country("United States")
+                .baro_altitude(10000.0)
+                .build()],
+            snapshot_time: None,
+            elapsed: Duration::ZERO,
+            location: None,
+        };
+
+        let mut csv = Vec::new();
+        formatter(cli.format, &cli)
+            .write(&mut csv, &result)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "callsign,baro_altitude\n\"UAL,123\",10000\n"
+        );
+
+        let cli = Cli::try_parse_from([
+            "ifo",
+            "--coords",
+            "0,0",
+            "--format",
+            "json",
+            "--fields",
+            "callsign,baro_altitude",
+        ])
+        .unwrap();
+        let mut json = Vec::new();
+        formatter(cli.format, &cli)
+            .write(&mut json, &result)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!([{"callsign": "UAL,123", "baro_altitude": 10000.0}])
+        );
+    }
+
+    #[test]
+    fn test_output_targets_write_each_format() {
+        let json_path = std::env::temp_dir().join(format!("ifo-output-{}.json", process::id()));
+        let json_target = format!("json={}", json_path.display());
+        let result = QueryResult {
+            center: None,
+            location_name: "capture".to_string(),
+            aircraft: vec![Aircraft::builder("abc123").callsign("UAL123").build()],
+            snapshot_time: None,
+            elapsed: Duration::ZERO,
+            location: None,
+        };
+
+        // The targets are written whether stdout gets a listing or a count.
+        for count in [false, true] {
+            let mut args = vec!["ifo", "--coords", "0,0", "--format", "csv"];
+            if count {
+                args.push("--count");
+            }
+            args.extend(["--output", &json_target, "--output", "CSV=-"]);
+            let cli = Cli::try_parse_from(args).unwrap();
+
+            let mut stdout = Vec::new();
+            write_results(&mut stdout, &result, &cli).unwrap();
+            let stdout = String::from_utf8(stdout).unwrap();
+            let csv = if count {
+                stdout.strip_prefix("1\n").unwrap()
+            } else {
+                let (listing, copy) = stdout.split_at(stdout.len() / 2);
+                assert_eq!(listing, copy);
+                copy
+            };
+            assert!(csv.starts_with("icao24,"));
+            assert!(csv.lines().nth(1).unwrap().starts_with("abc123,"));
+
+            let json: serde_json::Value =
+                serde_json::from_slice(&std::fs::read(&json_path).unwrap()).unwrap();
+            assert_eq!(json[0]["callsign"], "UAL123");
+            std::fs::remove_file(&json_path).unwrap();
         }
 
-        println!();
+        assert!(Cli::try_parse_from(["ifo", "--coords", "0,0", "--output", "out.json"]).is_err());
+        assert!(Cli::try_parse_from(["ifo", "--coords", "0,0", "--output", "yaml=x"]).is_err());
+        assert!(Cli::try_parse_from(["ifo", "--coords", "0,0", "--output", "json="]).is_err());
     }
 
-    Ok(())
+    #[test]
+    fn test_analyze_capture() {
+        let captured = QueryResult {
+            center: None,
+            location_name: "capture".to_string(),
+            aircraft: vec![
+                Aircraft::builder("abc123")
+                    .callsign("UAL123")
+                    .origin_country("United States")
+                    .baro_altitude(11000.0)
+                    .build(),
+                Aircraft::builder("def456")
+                    .callsign("DLH9LF")
+                    .origin_country("Germany")
+                    .baro_altitude(3000.0)
+                    .build(),
+            ],
+            snapshot_time: None,
+            elapsed: Duration::ZERO,
+            location: None,
+        };
+        let writer = Cli::try_parse_from(["ifo", "--coords", "0,0", "--format", "ndjson"]).unwrap();
+        let mut ndjson = Vec::new();
+        formatter(writer.format, &writer)
+            .write(&mut ndjson, &captured)
+            .unwrap();
+
+        let cli = Cli::try_parse_from([
+            "ifo",
+            "--analyze",
+            "-",
+            "--format",
+            "csv",
+            "--fields",
+            "icao24,origin_country",
+            "--sort",
+            "altitude",
+        ])
+        .unwrap();
+        let mut result = capture_from(&ndjson[..], "stdin").unwrap();
+        refine(&mut result, &cli);
+        let mut csv = Vec::new();
+        formatter(cli.format, &cli)
+            .write(&mut csv, &result)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "icao24,origin_country\ndef456,Germany\nabc123,United States\n"
+        );
+
+        let cli = Cli::try_parse_from(["ifo", "--analyze", "-", "--min-fl", "100"]).unwrap();
+        let mut result = capture_from(&ndjson[..], "stdin").unwrap();
+        refine(&mut result, &cli);
+        let mut text = Vec::new();
+        formatter(cli.format, &cli)
+            .write(&mut text, &result)
+            .unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.starts_with("Found 1 aircraft in stdin"), "{}", text);
+        assert!(text.contains("UAL123"));
+        assert!(!text.contains("DLH9LF"));
+    }
+
+    #[test]
+    fn test_flights_format_choices() {
+        let flights = |format: &str| {
+            Cli::try_parse_from([
+                "ifo", "flights", "--icao24", "3c6444", "--since", "1h ago", "--format", format,
+            ])
+        };
+        assert!(flights("json").is_ok());
+        // Rejected while parsing, before any request is spent.
+        assert!(flights("csv").is_err());
+        assert!(flights("html").is_err());
+    }
+
+    #[test]
+    fn test_user_agent_option() {
+        let ua = "ifo-ops/1.0 (ops@example.com)";
+        let cli = Cli::try_parse_from(["ifo", "--coords", "0,0", "--user-agent", ua]).unwrap();
+        assert_eq!(cli.user_agent.as_deref(), Some(ua));
+
+        let cli = Cli::try_parse_from([
+            "ifo",
+            "flights",
+            "--icao24",
+            "3c6444",
+            "--since",
+            "1h ago",
+            "--user-agent",
+            ua,
+        ])
+        .unwrap();
+        assert_eq!(cli.user_agent.as_deref(), Some(ua));
+    }
+
+    #[test]
+    fn test_unknown_field_is_rejected() {
+        assert!(
+            Cli::try_parse_from(["ifo", "--coords", "0,0", "--fields", "icao24,altitude"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_template_renders_aircraft() {
+        let cli = Cli::try_parse_from([
+            "ifo",
+            "--coords",
+            "0,0",
+            "--template",
+            "{callsign} {altitude_ft}ft {heading}° {{{distance_km} km}} {squawk}",
+        ])
+        .unwrap();
+        let template = cli.template.unwrap();
+
+        let ac = Aircraft::builder("abc123")
+            .callsign("UAL123")
+            .position(0.0, 1.0)
+            .baro_altitude(10000.0)
+            .true_track(271.6)
+            .build();
+        let center = Coordinate::new(0.0, 0.0).unwrap();
+        assert_eq!(
+            template.render(&ac, Some(center)),
+            "UAL123 32808ft 272° {111.2 km} N/A"
+        );
+        assert_eq!(
+            template.render(&ac, None),
+            "UAL123 32808ft 272° {N/A km} N/A"
+        );
+    }
+
+    #[test]
+    fn test_unknown_template_placeholder_is_rejected() {
+        assert!(matches!(
+            Template::parse("{callsign} {altitude_m}"),
+            Err(IfoError::UnknownField(name)) if name == "altitude_m"
+        ));
+        assert!(Cli::try_parse_from(["ifo", "--coords", "0,0", "--template", "{speed}"]).is_err());
+        assert_eq!(
+            Template::parse("{ unclosed")
+                .unwrap()
+                .render(&Aircraft::builder("a").build(), None),
+            "{ unclosed"
+        );
+    }
+
+    #[test]
+    fn test_flights_subcommand() {
+        let cli =
+            Cli::try_parse_from(["ifo", "flights", "--icao24", "3C6444", "--since", "2h ago"])
+                .unwrap();
+        let Some(Command::Flights(args)) = cli.command else {
+            panic!("expected the flights subcommand");
+        };
+        assert_eq!(args.icao24, "3c6444");
+        assert_eq!(args.until, "now");
+
+        assert!(
+            Cli::try_parse_from(["ifo", "flights", "--icao24", "3c64", "--since", "2h ago"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_radius_unit_km() {
+        let cli = Cli::try_parse_from([
+            "ifo",
+            "--coords",
+            "0,0",
+            "--radius",
+            "50",
+            "--radius-unit",
+            "km",
+        ])
+        .unwrap();
+        let radius = search_radius(cli.radius, cli.radius_unit);
+        assert_eq!(radius, SearchRadius::Kilometers(50.0));
+
+        let bbox = radius.bbox_around(Coordinate::new(0.0, 0.0).unwrap());
+        let height_km = Coordinate::new(bbox.lat_min, 0.0)
+            .unwrap()
+            .distance_to(&Coordinate::new(bbox.lat_max, 0.0).unwrap());
+        let width_km = Coordinate::new(0.0, bbox.lon_min)
+            .unwrap()
+            .distance_to(&Coordinate::new(0.0, bbox.lon_max).unwrap());
+        assert!((height_km - 100.0).abs() < 0.01);
+        assert!((width_km - 100.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_min_flight_level() {
+        let cli = Cli::try_parse_from(["ifo", "--coords", "0,0", "--min-fl", "100"]).unwrap();
+        let aircraft = vec![
+            Aircraft::builder("000001").baro_altitude(2_000.0).build(),
+            Aircraft::builder("000002").baro_altitude(3_500.0).build(),
+        ];
+        let kept = models::filter_flight_levels(aircraft, cli.min_fl, cli.max_fl);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].icao24, "000002");
+    }
+
+    #[test]
+    fn test_count_prints_only_the_number() {
+        let cli = Cli::try_parse_from(["ifo", "--place", "London", "--count"]).unwrap();
+        assert!(cli.count);
+        let mut out = Vec::new();
+        write_count(&mut out, 42, cli.format).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "42\n");
+
+        let mut out = Vec::new();
+        write_count(&mut out, 42, FormatArg::Json).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"count\":42}\n");
+
+        assert!(Cli::try_parse_from(["ifo", "--place", "London", "--count", "--dry-run"]).is_err());
+    }
+
+    #[test]
+    fn test_radius_unit_defaults_to_degrees() {
+        let cli = Cli::try_parse_from(["ifo", "--coords", "0,0"]).unwrap();
+        assert_eq!(
+            search_radius(cli.radius, cli.radius_unit),
+            SearchRadius::Degrees(0.5)
+        );
+    }
+
+    #[test]
+    fn test_text_format_options() {
+        let format = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["ifo", "--coords", "0,0"].iter().chain(args)).unwrap();
+            text_format(&cli)
+        };
+
+        assert_eq!(format(&[]), TextFormat::default());
+        assert_eq!(
+            format(&[
+                "--distance-unit",
+                "nm",
+                "--distance-precision",
+                "2",
+                "--group-by",
+                "country"
+            ]),
+            TextFormat {
+                group_by_country: true,
+                distance_unit: DistanceUnit::NauticalMiles,
+                distance_precision: 2,
+            }
+        );
+    }
 }
@@ -1,7 +1,98 @@
 //! Data models for the IFO application.
 
 use crate::error::{IfoError, Result};
+use crate::limits::MAX_TILES_PER_QUERY;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::BufRead;
+
+/// Lowest plausible altitude in metres (below the Dead Sea shore, with margin).
+pub const MIN_PLAUSIBLE_ALTITUDE_M: f64 = -500.0;
+
+/// Highest plausible altitude in metres for civil traffic.
+pub const MAX_PLAUSIBLE_ALTITUDE_M: f64 = 20_000.0;
+
+/// Vertical rates within this many m/s of zero count as level flight.
+pub const LEVEL_FLIGHT_DEADBAND_MPS: f64 = 0.5;
+
+/// Base-32 alphabet used by geohash (no a, i, l or o).
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Mean Earth radius in kilometres, as used by the haversine formula.
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Kilometres in one nautical mile.
+pub const KM_PER_NM: f64 = 1.852;
+
+/// Metres in one international foot.
+pub const METRES_PER_FOOT: f64 = 0.3048;
+
+/// Metres per second in one knot (one nautical mile per hour).
+const MPS_PER_KNOT: f64 = KM_PER_NM * 1000.0 / 3600.0;
+
+/// Metres per second in one statute mile per hour.
+const MPS_PER_MPH: f64 = 1609.344 / 3600.0;
+
+/// Ground speed below which [`Aircraft::eta_to`] treats an aircraft as
+/// stationary, in m/s.
+const STATIONARY_SPEED_MPS: f64 = 1.0;
+
+/// Net turn, in degrees, after which [`is_holding`] calls a track a hold:
+/// one and a half laps, i.e. at least three heading reversals.
+const HOLDING_MIN_TURN_DEG: f64 = 540.0;
+
+/// Furthest a holding track may stray from its first point, in km.
+const HOLDING_MAX_EXTENT_KM: f64 = 30.0;
+
+/// Legs shorter than this (km) are too noisy to give a heading and are
+/// merged into the next one by [`is_holding`].
+const HOLDING_MIN_LEG_KM: f64 = 0.05;
+
+/// A speed, stored in metres per second.
+///
+/// Serializes as the bare m/s number, the unit OpenSky reports.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Speed(f64);
+
+impl Speed {
+    pub fn from_mps(mps: f64) -> Self {
+        Self(mps)
+    }
+
+    pub fn from_knots(knots: f64) -> Self {
+        Self(knots * MPS_PER_KNOT)
+    }
+
+    pub fn from_kmh(kmh: f64) -> Self {
+        Self(kmh / 3.6)
+    }
+
+    pub fn from_mph(mph: f64) -> Self {
+        Self(mph * MPS_PER_MPH)
+    }
+
+    /// Metres per second.
+    pub fn mps(self) -> f64 {
+        self.0
+    }
+
+    /// Knots.
+    pub fn knots(self) -> f64 {
+        self.0 / MPS_PER_KNOT
+    }
+
+    /// Kilometres per hour.
+    pub fn kmh(self) -> f64 {
+        self.0 * 3.6
+    }
+
+    /// Statute miles per hour.
+    pub fn mph(self) -> f64 {
+        self.0 / MPS_PER_MPH
+    }
+}
 
 /// Represents a geographic coordinate.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -24,6 +115,192 @@ impl Coordinate {
             longitude,
         })
     }
+
+    /// Parse a coordinate string in format 'lat,lon'.
+    pub fn parse(coord_str: &str) -> Result<Self> {
+        let parts: Vec<&str> = coord_str.split(',').collect();
+        if parts.len() != 2 {
+            return Err(IfoError::InvalidCoordinates(
+                "Coordinates must be in format 'latitude,longitude'".to_string(),
+            ));
+        }
+
+        let lat = parts[0]
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| IfoError::InvalidCoordinates(format!("Invalid latitude: {}", parts[0])))?;
+
+        let lon = parts[1].trim().parse::<f64>().map_err(|_| {
+            IfoError::InvalidCoordinates(format!("Invalid longitude: {}", parts[1]))
+        })?;
+
+        Self::new(lat, lon)
+    }
+
+    /// The diametrically opposite point on the globe.
+    pub fn antipode(&self) -> Coordinate {
+        let longitude = if self.longitude > 0.0 {
+            self.longitude - 180.0
+        } else {
+            self.longitude + 180.0
+        };
+        Coordinate {
+            latitude: -self.latitude,
+            longitude,
+        }
+    }
+
+    /// Great-circle distance to another coordinate in kilometres (haversine).
+    pub fn distance_to(&self, other: &Coordinate) -> f64 {
+        let lat1 = self.latitude.to_radians();
+        let lat2 = other.latitude.to_radians();
+        let dlat = lat2 - lat1;
+        let dlon = (other.longitude - self.longitude).to_radians();
+
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+    }
+
+    /// The point reached by travelling `distance_km` along the great circle
+    /// leaving here at `bearing_deg` (clockwise from north).
+    pub fn destination(&self, bearing_deg: f64, distance_km: f64) -> Coordinate {
+        let lat1 = self.latitude.to_radians();
+        let lon1 = self.longitude.to_radians();
+        let bearing = bearing_deg.to_radians();
+        let angular = distance_km / EARTH_RADIUS_KM;
+
+        let lat2 = (lat1.sin() * angular.cos() + lat1.cos() * angular.sin() * bearing.cos())
+            .clamp(-1.0, 1.0)
+            .asin();
+        let lon2 = lon1
+            + (bearing.sin() * angular.sin() * lat1.cos())
+                .atan2(angular.cos() - lat1.sin() * lat2.sin());
+
+        Coordinate {
+            latitude: lat2.to_degrees(),
+            longitude: (lon2.to_degrees() + 540.0).rem_euclid(360.0) - 180.0,
+        }
+    }
+
+    /// The point `fraction` of the way along the great circle to `other`:
+    /// 0 is here, 1 is `other`.
+    ///
+    /// Antipodal endpoints have no unique great circle; the result is then
+    /// arbitrary.
+    pub fn intermediate(&self, other: &Coordinate, fraction: f64) -> Coordinate {
+        let angular = self.distance_to(other) / EARTH_RADIUS_KM;
+        if angular < 1e-12 {
+            return *self;
+        }
+        let (lat1, lon1) = (self.latitude.to_radians(), self.longitude.to_radians());
+        let (lat2, lon2) = (other.latitude.to_radians(), other.longitude.to_radians());
+
+        let a = ((1.0 - fraction) * angular).sin() / angular.sin();
+        let b = (fraction * angular).sin() / angular.sin();
+        let x = a * lat1.cos() * lon1.cos() + b * lat2.cos() * lon2.cos();
+        let y = a * lat1.cos() * lon1.sin() + b * lat2.cos() * lon2.sin();
+        let z = a * lat1.sin() + b * lat2.sin();
+
+        Coordinate {
+            latitude: z.atan2(x.hypot(y)).to_degrees(),
+            longitude: y.atan2(x).to_degrees(),
+        }
+    }
+
+    /// `segments + 1` points along the great circle to `other`, both
+    /// endpoints included, for drawing curved routes.
+    ///
+    /// With `segments == 0` only the two endpoints are returned. Identical
+    /// endpoints yield repeated copies of the same point.
+    pub fn great_circle_path(&self, other: &Coordinate, segments: usize) -> Vec<Coordinate> {
+        if segments == 0 {
+            return vec![*self, *other];
+        }
+        // Endpoints are copied rather than interpolated, so they come back
+        // exactly as given.
+        let mut path = Vec::with_capacity(segments + 1);
+        path.push(*self);
+        path.extend((1..segments).map(|i| self.intermediate(other, i as f64 / segments as f64)));
+        path.push(*other);
+        path
+    }
+
+    /// Rhumb-line (constant bearing) distance to another coordinate in
+    /// kilometres.
+    ///
+    /// Never shorter than [`distance_to`](Self::distance_to); the two agree
+    /// along the equator and meridians.
+    pub fn rhumb_distance_to(&self, other: &Coordinate) -> f64 {
+        let (dlat, dpsi, dlon) = self.rhumb_deltas(other);
+        // On an east-west line the Mercator stretch dpsi vanishes, so dlat/dpsi
+        // degenerates to 0/0; its limit is the cosine of the latitude.
+        let q = if dpsi.abs() > 1e-12 {
+            dlat / dpsi
+        } else {
+            self.latitude.to_radians().cos()
+        };
+        EARTH_RADIUS_KM * (dlat * dlat + q * q * dlon * dlon).sqrt()
+    }
+
+    /// Constant compass bearing in degrees (`0..360`, clockwise from north)
+    /// of the rhumb line to another coordinate.
+    pub fn rhumb_bearing_to(&self, other: &Coordinate) -> f64 {
+        let (_, dpsi, dlon) = self.rhumb_deltas(other);
+        dlon.atan2(dpsi).to_degrees().rem_euclid(360.0)
+    }
+
+    /// Latitude change, projected (Mercator) latitude change and shortest
+    /// longitude change to `other`, all in radians.
+    fn rhumb_deltas(&self, other: &Coordinate) -> (f64, f64, f64) {
+        use std::f64::consts::{FRAC_PI_4, PI};
+
+        let lat1 = self.latitude.to_radians();
+        let lat2 = other.latitude.to_radians();
+        let dpsi = ((FRAC_PI_4 + lat2 / 2.0).tan() / (FRAC_PI_4 + lat1 / 2.0).tan()).ln();
+
+        let mut dlon = (other.longitude - self.longitude).to_radians();
+        if dlon > PI {
+            dlon -= 2.0 * PI;
+        } else if dlon < -PI {
+            dlon += 2.0 * PI;
+        }
+        (lat2 - lat1, dpsi, dlon)
+    }
+
+    /// Encode as a geohash of `precision` characters.
+    ///
+    /// Each character narrows the cell by 5 bits, alternating longitude and
+    /// latitude starting with longitude. Coordinates sharing a prefix fall in
+    /// the same cell at that precision.
+    pub fn to_geohash(&self, precision: usize) -> String {
+        let mut lat_range = (-90.0, 90.0);
+        let mut lon_range = (-180.0, 180.0);
+        let mut hash = String::with_capacity(precision);
+        let mut even_bit = true;
+
+        while hash.len() < precision {
+            let mut index = 0;
+            for _ in 0..5 {
+                let (range, value) = if even_bit {
+                    (&mut lon_range, self.longitude)
+                } else {
+                    (&mut lat_range, self.latitude)
+                };
+                let mid = (range.0 + range.1) / 2.0;
+                index <<= 1;
+                if value >= mid {
+                    index |= 1;
+                    range.0 = mid;
+                } else {
+                    range.1 = mid;
+                }
+                even_bit = !even_bit;
+            }
+            hash.push(GEOHASH_ALPHABET[index] as char);
+        }
+
+        hash
+    }
 }
 
 /// Represents a geographic bounding box.
@@ -74,12 +351,107 @@ impl BoundingBox {
         })
     }
 
+    /// Parse a bounding box string in format 'lat_min,lon_min,lat_max,lon_max',
+    /// validated as by [`new`](Self::new).
+    pub fn parse(bbox_str: &str) -> Result<Self> {
+        let parts: Vec<&str> = bbox_str.split(',').collect();
+        if parts.len() != 4 {
+            return Err(IfoError::InvalidBoundingBox(
+                "Bounding box must be in format 'lat_min,lon_min,lat_max,lon_max'".to_string(),
+            ));
+        }
+
+        let mut values = [0.0; 4];
+        for (value, part) in values.iter_mut().zip(&parts) {
+            *value = part
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| IfoError::InvalidBoundingBox(format!("Invalid number: {}", part)))?;
+        }
+
+        let [lat_min, lon_min, lat_max, lon_max] = values;
+        Self::new(lat_min, lon_min, lat_max, lon_max)
+    }
+
+    /// Midpoint of the box in latitude and longitude.
+    pub fn center(&self) -> Coordinate {
+        Coordinate {
+            latitude: (self.lat_min + self.lat_max) / 2.0,
+            longitude: (self.lon_min + self.lon_max) / 2.0,
+        }
+    }
+
+    /// Split the box into a grid of equal tiles, none wider or taller than
+    /// `max_span` degrees, ordered row by row from the south-west corner.
+    ///
+    /// Adjacent tiles share their edges, so an aircraft exactly on one may be
+    /// reported by both.
+    pub fn tiles(&self, max_span: f64) -> Result<Vec<BoundingBox>> {
+        if !(max_span.is_finite() && max_span > 0.0) {
+            return Err(IfoError::InvalidBoundingBox(format!(
+                "tile span must be positive, got {}",
+                max_span
+            )));
+        }
+        let rows = ((self.lat_max - self.lat_min) / max_span).ceil().max(1.0);
+        let cols = ((self.lon_max - self.lon_min) / max_span).ceil().max(1.0);
+        if rows * cols > MAX_TILES_PER_QUERY as f64 {
+            return Err(IfoError::InvalidBoundingBox(format!(
+                "tile span {} splits the box into more than {} tiles",
+                max_span, MAX_TILES_PER_QUERY
+            )));
+        }
+        Ok(self.grid(rows as usize, cols as usize).collect())
+    }
+
     /// Create a bounding box from a center coordinate and radius.
     pub fn from_center(center: Coordinate, radius_deg: f64) -> Self {
-        let lat_min = (center.latitude - radius_deg).max(-90.0);
-        let lat_max = (center.latitude + radius_deg).min(90.0);
-        let lon_min = (center.longitude - radius_deg).max(-180.0);
-        let lon_max = (center.longitude + radius_deg).min(180.0);
+        Self::from_center_span(center, radius_deg, radius_deg)
+    }
+
+    /// Create a bounding box reaching `radius_km` from the center on each side.
+    ///
+    /// Longitude degrees shrink towards the poles, so the box is wider in
+    /// degrees than it is tall at high latitudes.
+    pub fn from_center_km(center: Coordinate, radius_km: f64) -> Self {
+        let km_per_deg = EARTH_RADIUS_KM.to_radians();
+        let lat_deg = radius_km / km_per_deg;
+        let lon_deg = (radius_km / (km_per_deg * center.latitude.to_radians().cos())).min(180.0);
+        Self::from_center_span(center, lat_deg, lon_deg)
+    }
+
+    /// Create a bounding box reaching `radius_nm` nautical miles from the center.
+    pub fn from_center_nm(center: Coordinate, radius_nm: f64) -> Self {
+        Self::from_center_km(center, radius_nm * KM_PER_NM)
+    }
+
+    /// Smallest bounding box containing every vertex of `polygon`.
+    ///
+    /// Longitudes are compared as plain numbers, so a polygon crossing the
+    /// antimeridian gets a box spanning the rest of the globe instead.
+    pub fn enclosing(polygon: &[Coordinate]) -> Result<Self> {
+        if polygon.len() < 3 {
+            return Err(IfoError::InvalidBoundingBox(format!(
+                "polygon needs at least 3 vertices, got {}",
+                polygon.len()
+            )));
+        }
+        let (mut lat_min, mut lon_min) = (f64::INFINITY, f64::INFINITY);
+        let (mut lat_max, mut lon_max) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for vertex in polygon {
+            lat_min = lat_min.min(vertex.latitude);
+            lat_max = lat_max.max(vertex.latitude);
+            lon_min = lon_min.min(vertex.longitude);
+            lon_max = lon_max.max(vertex.longitude);
+        }
+        Self::new(lat_min, lon_min, lat_max, lon_max)
+    }
+
+    fn from_center_span(center: Coordinate, lat_deg: f64, lon_deg: f64) -> Self {
+        let lat_min = (center.latitude - lat_deg).max(-90.0);
+        let lat_max = (center.latitude + lat_deg).min(90.0);
+        let lon_min = (center.longitude - lon_deg).max(-180.0);
+        let lon_max = (center.longitude + lon_deg).min(180.0);
 
         // Safe to unwrap because we're clamping values
         Self {
@@ -89,26 +461,244 @@ impl BoundingBox {
             lon_max,
         }
     }
+
+    /// Whether `point` lies inside the box, edges included.
+    pub fn contains(&self, point: Coordinate) -> bool {
+        (self.lat_min..=self.lat_max).contains(&point.latitude)
+            && (self.lon_min..=self.lon_max).contains(&point.longitude)
+    }
+
+    /// Split the box into `rows` x `cols` equal cells, row by row from the
+    /// south-west corner. Either count being zero yields no cells.
+    ///
+    /// Neighbouring cells share their edges exactly, and the outer edges are
+    /// the box's own, free of rounding.
+    pub fn grid(&self, rows: usize, cols: usize) -> impl Iterator<Item = BoundingBox> + '_ {
+        let edge = |min: f64, max: f64, n: usize, i: usize| {
+            if i == n {
+                max
+            } else {
+                min + (max - min) * i as f64 / n as f64
+            }
+        };
+        (0..rows).flat_map(move |row| {
+            (0..cols).map(move |col| BoundingBox {
+                lat_min: edge(self.lat_min, self.lat_max, rows, row),
+                lon_min: edge(self.lon_min, self.lon_max, cols, col),
+                lat_max: edge(self.lat_min, self.lat_max, rows, row + 1),
+                lon_max: edge(self.lon_min, self.lon_max, cols, col + 1),
+            })
+        })
+    }
+
+    /// Grow every side by `margin_deg`, clamping at the valid lat/lon limits.
+    ///
+    /// A negative margin shrinks the box instead; shrinking so far that the
+    /// box would invert or collapse is an error.
+    pub fn expanded(&self, margin_deg: f64) -> Result<Self> {
+        Self::new(
+            (self.lat_min - margin_deg).max(-90.0),
+            (self.lon_min - margin_deg).max(-180.0),
+            (self.lat_max + margin_deg).min(90.0),
+            (self.lon_max + margin_deg).min(180.0),
+        )
+    }
+}
+
+/// Size of a search area around a center point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchRadius {
+    Degrees(f64),
+    Kilometers(f64),
+    NauticalMiles(f64),
+}
+
+impl SearchRadius {
+    /// The bounding box this radius describes around `center`.
+    pub fn bbox_around(self, center: Coordinate) -> BoundingBox {
+        match self {
+            SearchRadius::Degrees(deg) => BoundingBox::from_center(center, deg),
+            SearchRadius::Kilometers(km) => BoundingBox::from_center_km(center, km),
+            SearchRadius::NauticalMiles(nm) => BoundingBox::from_center_nm(center, nm),
+        }
+    }
+}
+
+/// Unit for reporting distances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceUnit {
+    Kilometers,
+    Meters,
+    NauticalMiles,
+}
+
+impl DistanceUnit {
+    /// Convert a distance in kilometres into this unit.
+    pub fn from_km(self, km: f64) -> f64 {
+        match self {
+            DistanceUnit::Kilometers => km,
+            DistanceUnit::Meters => km * 1000.0,
+            DistanceUnit::NauticalMiles => km / KM_PER_NM,
+        }
+    }
+
+    /// Short unit symbol, e.g. `km`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            DistanceUnit::Kilometers => "km",
+            DistanceUnit::Meters => "m",
+            DistanceUnit::NauticalMiles => "nm",
+        }
+    }
 }
 
 /// Represents an aircraft state.
+///
+/// Optional fields may be omitted entirely when deserializing, so trimmed or
+/// logged payloads round-trip. New fields may be added in minor releases, so
+/// construct values with [`Aircraft::builder`] rather than a struct literal.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Aircraft {
     pub icao24: String,
+    #[serde(default)]
     pub callsign: Option<String>,
     pub origin_country: String,
+    /// Unix timestamp of the last position update.
+    #[serde(default)]
+    pub time_position: Option<i64>,
+    /// Unix timestamp of the last message of any kind from the transponder.
+    #[serde(default)]
+    pub last_contact: Option<i64>,
+    #[serde(default)]
     pub longitude: Option<f64>,
+    #[serde(default)]
     pub latitude: Option<f64>,
+    #[serde(default)]
     pub baro_altitude: Option<f64>,
     pub on_ground: bool,
+    /// Ground speed in m/s: speed over the ground, not airspeed, so it
+    /// includes the effect of wind. See [`ground_speed_mps`](Self::ground_speed_mps).
+    #[serde(default)]
     pub velocity: Option<f64>,
+    #[serde(default)]
     pub true_track: Option<f64>,
+    #[serde(default)]
     pub vertical_rate: Option<f64>,
+    #[serde(default)]
     pub geo_altitude: Option<f64>,
+    #[serde(default)]
     pub squawk: Option<String>,
 }
 
 impl Aircraft {
+    /// Start building an aircraft with the given ICAO24 address. Every
+    /// other field starts empty, with `on_ground` false.
+    ///
+    /// ```
+    /// use ifo::Aircraft;
+    ///
+    /// let ac = Aircraft::builder("4008f6")
+    ///     .callsign("BAW123")
+    ///     .origin_country("United Kingdom")
+    ///     .position(51.47, -0.45)
+    ///     .baro_altitude(1200.0)
+    ///     .build();
+    /// assert_eq!(ac.airline(), Some("British Airways"));
+    /// ```
+    pub fn builder(icao24: impl Into<String>) -> AircraftBuilder {
+        AircraftBuilder(Aircraft {
+            icao24: icao24.into(),
+            callsign: None,
+            origin_country: String::new(),
+            time_position: None,
+            last_contact: None,
+            longitude: None,
+            latitude: None,
+            baro_altitude: None,
+            on_ground: false,
+            velocity: None,
+            true_track: None,
+            vertical_rate: None,
+            geo_altitude: None,
+            squawk: None,
+        })
+    }
+
+    /// Column names matching [`to_row`](Self::to_row), for CSV or table
+    /// output.
+    pub fn header() -> Vec<&'static str> {
+        vec![
+            "icao24",
+            "callsign",
+            "origin_country",
+            "time_position",
+            "last_contact",
+            "latitude",
+            "longitude",
+            "baro_altitude",
+            "geo_altitude",
+            "on_ground",
+            "velocity",
+            "true_track",
+            "vertical_rate",
+            "squawk",
+        ]
+    }
+
+    /// This aircraft as one string per [`header`](Self::header) column.
+    ///
+    /// Missing values are empty strings; numbers use their shortest
+    /// round-tripping form and `on_ground` is `true`/`false`.
+    pub fn to_row(&self) -> Vec<String> {
+        fn cell<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map(T::to_string).unwrap_or_default()
+        }
+
+        vec![
+            self.icao24.clone(),
+            cell(&self.callsign),
+            self.origin_country.clone(),
+            cell(&self.time_position),
+            cell(&self.last_contact),
+            cell(&self.latitude),
+            cell(&self.longitude),
+            cell(&self.baro_altitude),
+            cell(&self.geo_altitude),
+            self.on_ground.to_string(),
+            cell(&self.velocity),
+            cell(&self.true_track),
+            cell(&self.vertical_rate),
+            cell(&self.squawk),
+        ]
+    }
+
+    /// The named field as a JSON value, `null` when missing.
+    ///
+    /// Names are those of [`header`](Self::header); anything else fails
+    /// with [`IfoError::UnknownField`].
+    pub fn field(&self, name: &str) -> Result<serde_json::Value> {
+        use serde_json::Value;
+
+        Ok(match name {
+            "icao24" => Value::from(self.icao24.as_str()),
+            "callsign" => Value::from(self.callsign.clone()),
+            "origin_country" => Value::from(self.origin_country.as_str()),
+            "time_position" => Value::from(self.time_position),
+            "last_contact" => Value::from(self.last_contact),
+            "latitude" => Value::from(self.latitude),
+            "longitude" => Value::from(self.longitude),
+            "baro_altitude" => Value::from(self.baro_altitude),
+            "geo_altitude" => Value::from(self.geo_altitude),
+            "on_ground" => Value::from(self.on_ground),
+            "velocity" => Value::from(self.velocity),
+            "true_track" => Value::from(self.true_track),
+            "vertical_rate" => Value::from(self.vertical_rate),
+            "squawk" => Value::from(self.squawk.clone()),
+            _ => return Err(IfoError::UnknownField(name.to_string())),
+        })
+    }
+
     /// Parse a state vector from the OpenSky API.
     pub fn from_state_vector(state: Vec<serde_json::Value>) -> Result<Self> {
         if state.len() < 17 {
@@ -125,6 +715,8 @@ impl Aircraft {
                 .map(|s| s.trim().to_string())
                 .filter(|s| !s.is_empty()),
             origin_country: state[2].as_str().unwrap_or("").to_string(),
+            time_position: state[3].as_i64(),
+            last_contact: state[4].as_i64(),
             longitude: state[5].as_f64(),
             latitude: state[6].as_f64(),
             baro_altitude: state[7].as_f64(),
@@ -136,27 +728,2988 @@ impl Aircraft {
             squawk: state[14].as_str().map(|s| s.to_string()),
         })
     }
+
+    /// When the reported position was measured, as a Unix timestamp.
+    ///
+    /// Use this rather than `last_contact` to judge how stale the position
+    /// is: an aircraft can keep sending other messages without a new fix.
+    pub fn position_timestamp(&self) -> Option<i64> {
+        self.time_position
+    }
+
+    /// Airline operating the flight, from the ICAO designator that starts
+    /// the callsign (`BAW123` is British Airways).
+    ///
+    /// Returns `None` without a callsign or when the prefix is not in the
+    /// bundled table.
+    pub fn airline(&self) -> Option<&'static str> {
+        let prefix = self.callsign.as_deref()?.get(..3)?.to_ascii_uppercase();
+        AIRLINE_DESIGNATORS
+            .binary_search_by_key(&prefix.as_str(), |&(code, _)| code)
+            .ok()
+            .map(|idx| AIRLINE_DESIGNATORS[idx].1)
+    }
+
+    /// Split the callsign into airline designator and flight number, e.g.
+    /// `BAW123` into `("BAW", "123")`.
+    ///
+    /// Only a three-letter ICAO designator followed by a digit is split off.
+    /// Anything else, such as a registration (`N12345`, `GBNLA`) or an
+    /// all-digit callsign, comes back whole as the first part with an empty
+    /// second part. Returns `None` without a callsign.
+    pub fn callsign_parts(&self) -> Option<(String, String)> {
+        let callsign = self.callsign.as_deref()?.trim();
+        if callsign.is_empty() {
+            return None;
+        }
+        let letters = callsign.bytes().take_while(u8::is_ascii_alphabetic).count();
+        let next_is_digit = callsign
+            .as_bytes()
+            .get(letters)
+            .is_some_and(u8::is_ascii_digit);
+        if letters == 3 && next_is_digit {
+            let (designator, flight) = callsign.split_at(3);
+            Some((designator.to_string(), flight.to_string()))
+        } else {
+            Some((callsign.to_string(), String::new()))
+        }
+    }
+
+    /// Whether the ICAO24 address falls in a block known to be used by
+    /// military aircraft.
+    ///
+    /// A heuristic for spotters, not an authoritative classification: many
+    /// military aircraft use civil addresses and the block list is
+    /// incomplete. Returns `false` when the address is unknown or malformed.
+    pub fn is_likely_military(&self) -> bool {
+        let Some(address) = parse_icao24(&self.icao24) else {
+            return false;
+        };
+        let idx = MILITARY_ICAO24_BLOCKS.partition_point(|&(_, last)| last < address);
+        MILITARY_ICAO24_BLOCKS
+            .get(idx)
+            .is_some_and(|&(first, _)| first <= address)
+    }
+
+    /// Whether the transponder is squawking an emergency code: 7500
+    /// (unlawful interference), 7600 (radio failure) or 7700 (general
+    /// emergency).
+    pub fn is_emergency(&self) -> bool {
+        matches!(self.squawk.as_deref(), Some("7500" | "7600" | "7700"))
+    }
+
+    /// Label for a widely known special-purpose squawk, such as VFR
+    /// conspicuity or an emergency code.
+    ///
+    /// Several meanings are regional: 1200 is VFR in North America while
+    /// Europe uses 7000, and elsewhere the same codes may be ordinary
+    /// assignments. Only the best-known codes are covered; ordinary
+    /// ATC-assigned codes return `None`.
+    pub fn squawk_meaning(&self) -> Option<&'static str> {
+        Some(match self.squawk.as_deref()? {
+            "1200" => "VFR (North America)",
+            "7000" => "VFR (Europe)",
+            "2000" => "No code assigned",
+            "1000" => "IFR conspicuity (Mode S airspace)",
+            "7500" => "Unlawful interference",
+            "7600" => "Radio failure",
+            "7700" => "General emergency",
+            _ => return None,
+        })
+    }
+
+    /// "↑" when climbing, "↓" when descending, "→" when level within
+    /// [`LEVEL_FLIGHT_DEADBAND_MPS`], and "" when the vertical rate is
+    /// unknown.
+    pub fn vertical_trend_symbol(&self) -> &'static str {
+        match self.vertical_rate {
+            Some(rate) if rate > LEVEL_FLIGHT_DEADBAND_MPS => "↑",
+            Some(rate) if rate < -LEVEL_FLIGHT_DEADBAND_MPS => "↓",
+            Some(_) => "→",
+            None => "",
+        }
+    }
+
+    /// Flag physically implausible values in the reported state.
+    ///
+    /// Nothing is rejected; an empty list means no problems were found and
+    /// the caller decides what to do with the rest.
+    pub fn sanity_check(&self) -> Vec<Anomaly> {
+        let mut anomalies = Vec::new();
+
+        for altitude in [self.baro_altitude, self.geo_altitude]
+            .into_iter()
+            .flatten()
+        {
+            if !(MIN_PLAUSIBLE_ALTITUDE_M..=MAX_PLAUSIBLE_ALTITUDE_M).contains(&altitude) {
+                anomalies.push(Anomaly::AltitudeOutOfRange(altitude));
+            }
+        }
+        if let Some(velocity) = self.velocity.filter(|v| *v < 0.0) {
+            anomalies.push(Anomaly::NegativeVelocity(velocity));
+        }
+        if let Some(track) = self.true_track.filter(|t| !(0.0..360.0).contains(t)) {
+            anomalies.push(Anomaly::TrackOutOfRange(track));
+        }
+
+        anomalies
+    }
+
+    /// Estimate seconds until this aircraft is nearest to `observer`.
+    ///
+    /// Assumes the aircraft holds its current track and ground speed, and
+    /// works on a flat projection around the observer, so it is only
+    /// meaningful over a few hundred kilometres. Returns `None` when position,
+    /// track or velocity is missing, or when the aircraft is not approaching.
+    pub fn time_to_closest_approach(&self, observer: Coordinate) -> Option<f64> {
+        let (x, y) = local_offset_km(observer, self.position()?);
+        let (vx, vy) = self.velocity_vector_kms()?;
+
+        let speed_sq = vx * vx + vy * vy;
+        if speed_sq == 0.0 {
+            return None;
+        }
+
+        let t = -(x * vx + y * vy) / speed_sq;
+        (t > 0.0).then_some(t)
+    }
+
+    /// Estimate seconds until this aircraft reaches `dest`.
+    ///
+    /// Divides the great-circle distance by the current ground speed and
+    /// ignores the track, so it assumes the aircraft is heading straight for
+    /// `dest`; a rough estimate for plane spotting, not a flight plan ETA.
+    /// Returns `None` without a position or velocity, or when the aircraft
+    /// is practically stationary.
+    pub fn eta_to(&self, dest: Coordinate) -> Option<f64> {
+        let speed = self.ground_speed_mps()?;
+        if speed < STATIONARY_SPEED_MPS {
+            return None;
+        }
+        Some(self.position()?.distance_to(&dest) * 1000.0 / speed)
+    }
+
+    /// Whether this aircraft and `other` are getting closer right now.
+    ///
+    /// Compares their relative position with their relative ground velocity
+    /// on a flat projection; altitude is ignored. This is a rough estimate
+    /// for awareness displays, not a conflict-detection tool. Returns `None`
+    /// when either aircraft lacks position, track or velocity.
+    pub fn is_converging_with(&self, other: &Aircraft) -> Option<bool> {
+        let (x, y) = local_offset_km(self.position()?, other.position()?);
+        let (vx_self, vy_self) = self.velocity_vector_kms()?;
+        let (vx_other, vy_other) = other.velocity_vector_kms()?;
+
+        let closing_rate = x * (vx_other - vx_self) + y * (vy_other - vy_self);
+        Some(closing_rate < 0.0)
+    }
+
+    /// Distance to `other` split into horizontal and vertical components.
+    ///
+    /// Uses barometric altitude, the reference ATC separation is based on.
+    /// Returns `None` unless both aircraft have a position and a barometric
+    /// altitude.
+    pub fn separation_3d(&self, other: &Aircraft) -> Option<Separation> {
+        let horizontal_km = self.position()?.distance_to(&other.position()?);
+        let vertical_m = (self.baro_altitude? - other.baro_altitude?).abs();
+        Some(Separation {
+            horizontal_km,
+            vertical_m,
+            slant_m: (horizontal_km * 1000.0).hypot(vertical_m),
+        })
+    }
+
+    /// Angle in degrees above the horizon at which an observer at
+    /// `observer`, `observer_alt_m` metres above sea level, sees this
+    /// aircraft; negative when it is below the horizon.
+    ///
+    /// Works on a spherical Earth, so the drop of distant aircraft below the
+    /// horizon is included; refraction is not. Uses the geometric altitude,
+    /// falling back to the barometric one, which can be a few hundred metres
+    /// off. Returns `None` without a position or altitude.
+    pub fn elevation_from(&self, observer: Coordinate, observer_alt_m: f64) -> Option<f64> {
+        let altitude = self.geo_altitude.or(self.baro_altitude)?;
+        let radius_m = EARTH_RADIUS_KM * 1000.0;
+        let central_angle = observer.distance_to(&self.position()?) / EARTH_RADIUS_KM;
+
+        // Aircraft position in a plane through the Earth's center, with the
+        // observer on the vertical axis.
+        let aircraft_r = radius_m + altitude;
+        let up = aircraft_r * central_angle.cos() - (radius_m + observer_alt_m);
+        let across = aircraft_r * central_angle.sin();
+        Some(up.atan2(across).to_degrees())
+    }
+
+    /// Ground velocity as (east, north) components in km/s.
+    fn velocity_vector_kms(&self) -> Option<(f64, f64)> {
+        let speed_kms = self.velocity? / 1000.0;
+        let track = self.true_track?.to_radians();
+        Some((speed_kms * track.sin(), speed_kms * track.cos()))
+    }
+
+    /// Ground speed as a [`Speed`], for unit conversions.
+    pub fn speed(&self) -> Option<Speed> {
+        self.velocity.map(Speed::from_mps)
+    }
+
+    /// Ground speed in m/s, the same value as `velocity`.
+    ///
+    /// OpenSky reports speed over the ground, not indicated or true
+    /// airspeed; with a 50 kt headwind an airliner at 450 kt true airspeed
+    /// shows about 400 kt here.
+    pub fn ground_speed_mps(&self) -> Option<f64> {
+        self.velocity
+    }
+
+    /// Ground speed in knots.
+    pub fn ground_speed_knots(&self) -> Option<f64> {
+        self.speed().map(Speed::knots)
+    }
+
+    /// Ground speed in km/h.
+    pub fn ground_speed_kmh(&self) -> Option<f64> {
+        self.speed().map(Speed::kmh)
+    }
+
+    /// Reported position, if both latitude and longitude are known.
+    pub fn position(&self) -> Option<Coordinate> {
+        match (self.latitude, self.longitude) {
+            (Some(lat), Some(lon)) => Coordinate::new(lat, lon).ok(),
+            _ => None,
+        }
+    }
+
+    /// [`last_contact`](Self::last_contact) as an RFC 3339 UTC string; see
+    /// [`format_unix_time`].
+    #[cfg(feature = "time")]
+    pub fn last_contact_rfc3339(&self) -> Option<String> {
+        self.last_contact.and_then(format_unix_time)
+    }
+
+    /// [`time_position`](Self::time_position) as an RFC 3339 UTC string;
+    /// see [`format_unix_time`].
+    #[cfg(feature = "time")]
+    pub fn time_position_rfc3339(&self) -> Option<String> {
+        self.time_position.and_then(format_unix_time)
+    }
 }
 
-/// Represents a location from geocoding.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Location {
-    pub lat: f64,
-    pub lon: f64,
-    pub display_name: String,
+/// Separation between two aircraft; see [`Aircraft::separation_3d`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Separation {
+    /// Great-circle distance between the two positions.
+    pub horizontal_km: f64,
+    /// Absolute altitude difference.
+    pub vertical_m: f64,
+    /// Straight-line distance combining both, on a flat-earth approximation.
+    pub slant_m: f64,
 }
 
-/// Response from OpenSky API.
-#[derive(Debug, Deserialize)]
-pub struct OpenSkyResponse {
-    pub time: Option<i64>,
-    pub states: Option<Vec<Vec<serde_json::Value>>>,
+/// Builder for [`Aircraft`]; see [`Aircraft::builder`].
+#[derive(Debug, Clone)]
+pub struct AircraftBuilder(Aircraft);
+
+impl AircraftBuilder {
+    pub fn callsign(mut self, callsign: impl Into<String>) -> Self {
+        self.0.callsign = Some(callsign.into());
+        self
+    }
+
+    pub fn origin_country(mut self, country: impl Into<String>) -> Self {
+        self.0.origin_country = country.into();
+        self
+    }
+
+    /// Unix timestamp of the last position update.
+    pub fn time_position(mut self, timestamp: i64) -> Self {
+        self.0.time_position = Some(timestamp);
+        self
+    }
+
+    /// Unix timestamp of the last message of any kind.
+    pub fn last_contact(mut self, timestamp: i64) -> Self {
+        self.0.last_contact = Some(timestamp);
+        self
+    }
+
+    /// Set latitude and longitude together.
+    pub fn position(mut self, latitude: f64, longitude: f64) -> Self {
+        self.0.latitude = Some(latitude);
+        self.0.longitude = Some(longitude);
+        self
+    }
+
+    pub fn baro_altitude(mut self, metres: f64) -> Self {
+        self.0.baro_altitude = Some(metres);
+        self
+    }
+
+    pub fn geo_altitude(mut self, metres: f64) -> Self {
+        self.0.geo_altitude = Some(metres);
+        self
+    }
+
+    pub fn on_ground(mut self, on_ground: bool) -> Self {
+        self.0.on_ground = on_ground;
+        self
+    }
+
+    /// Ground speed in m/s.
+    pub fn velocity(mut self, metres_per_sec: f64) -> Self {
+        self.0.velocity = Some(metres_per_sec);
+        self
+    }
+
+    /// Track over ground in degrees clockwise from north.
+    pub fn true_track(mut self, degrees: f64) -> Self {
+        self.0.true_track = Some(degrees);
+        self
+    }
+
+    /// Climb (positive) or descent rate in m/s.
+    pub fn vertical_rate(mut self, metres_per_sec: f64) -> Self {
+        self.0.vertical_rate = Some(metres_per_sec);
+        self
+    }
+
+    pub fn squawk(mut self, squawk: impl Into<String>) -> Self {
+        self.0.squawk = Some(squawk.into());
+        self
+    }
+
+    pub fn build(self) -> Aircraft {
+        self.0
+    }
 }
 
-/// Response from Nominatim geocoding API.
-#[derive(Debug, Deserialize)]
-pub struct NominatimResult {
-    pub lat: String,
-    pub lon: String,
-    pub display_name: String,
+/// Offset of `point` from `origin` as (east, north) km on a flat projection.
+fn local_offset_km(origin: Coordinate, point: Coordinate) -> (f64, f64) {
+    let mut dlon = point.longitude - origin.longitude;
+    if dlon > 180.0 {
+        dlon -= 360.0;
+    } else if dlon < -180.0 {
+        dlon += 360.0;
+    }
+    let km_per_deg = EARTH_RADIUS_KM.to_radians();
+    let east = dlon * km_per_deg * origin.latitude.to_radians().cos();
+    let north = (point.latitude - origin.latitude) * km_per_deg;
+    (east, north)
+}
+
+/// Inverse of [`local_offset_km`]: the point `(east, north)` km from `origin`.
+fn local_point(origin: Coordinate, (east, north): (f64, f64)) -> Coordinate {
+    let km_per_deg = EARTH_RADIUS_KM.to_radians();
+    let latitude = (origin.latitude + north / km_per_deg).clamp(-90.0, 90.0);
+    let mut longitude = origin.longitude + east / (km_per_deg * origin.latitude.to_radians().cos());
+    if longitude > 180.0 {
+        longitude -= 360.0;
+    } else if longitude < -180.0 {
+        longitude += 360.0;
+    }
+    Coordinate {
+        latitude,
+        longitude,
+    }
+}
+
+/// An implausible value found by [`Aircraft::sanity_check`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anomaly {
+    /// Barometric or geometric altitude outside the plausible civil range.
+    AltitudeOutOfRange(f64),
+    /// Ground speed below zero.
+    NegativeVelocity(f64),
+    /// True track outside `0..360` degrees.
+    TrackOutOfRange(f64),
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::AltitudeOutOfRange(alt) => write!(
+                f,
+                "altitude {} m outside {} to {} m",
+                alt, MIN_PLAUSIBLE_ALTITUDE_M, MAX_PLAUSIBLE_ALTITUDE_M
+            ),
+            Anomaly::NegativeVelocity(vel) => write!(f, "negative velocity {} m/s", vel),
+            Anomaly::TrackOutOfRange(track) => write!(f, "track {}° outside 0-360", track),
+        }
+    }
+}
+
+/// First second of the year 10000, which RFC 3339's four-digit years
+/// cannot represent.
+#[cfg(feature = "time")]
+const RFC3339_END_SECS: u64 = 253_402_300_800;
+
+/// Format Unix seconds as an RFC 3339 UTC timestamp, e.g.
+/// `2023-11-14T22:13:20Z`. Returns `None` for times before 1970, which
+/// OpenSky never reports, and from the year 10000 on, which RFC 3339
+/// cannot represent.
+#[cfg(feature = "time")]
+pub fn format_unix_time(secs: i64) -> Option<String> {
+    let secs = u64::try_from(secs)
+        .ok()
+        .filter(|&secs| secs < RFC3339_END_SECS)?;
+    let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+    Some(humantime::format_rfc3339_seconds(time).to_string())
+}
+
+/// Read aircraft from newline-delimited JSON, one object per line, as
+/// written by `--format ndjson`.
+///
+/// Blank lines are ignored. Optional fields may be absent, so captures
+/// trimmed with `--fields` still load as long as they keep `icao24`,
+/// `origin_country` and `on_ground`.
+pub fn read_ndjson(reader: impl BufRead) -> Result<Vec<Aircraft>> {
+    let mut aircraft = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        aircraft.push(serde_json::from_str(&line)?);
+    }
+    Ok(aircraft)
+}
+
+/// Label used by [`group_by_country`] for aircraft without an origin country.
+pub const UNKNOWN_COUNTRY: &str = "(unknown)";
+
+/// Group aircraft by origin country.
+///
+/// Countries iterate in alphabetical order and each group is sorted by
+/// callsign, with aircraft lacking a callsign last. Aircraft with an empty
+/// country are grouped under [`UNKNOWN_COUNTRY`].
+pub fn group_by_country(aircraft: Vec<Aircraft>) -> BTreeMap<String, Vec<Aircraft>> {
+    let mut groups: BTreeMap<String, Vec<Aircraft>> = BTreeMap::new();
+    for ac in aircraft {
+        let country = match ac.origin_country.trim() {
+            "" => UNKNOWN_COUNTRY.to_string(),
+            country => country.to_string(),
+        };
+        groups.entry(country).or_default().push(ac);
+    }
+
+    for group in groups.values_mut() {
+        group.sort_by(|a, b| match (&a.callsign, &b.callsign) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+    }
+    groups
+}
+
+/// Count positioned aircraft per geohash cell of the given precision.
+///
+/// Aircraft without a position are skipped.
+pub fn geohash_density(aircraft: &[Aircraft], precision: usize) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for pos in aircraft.iter().filter_map(Aircraft::position) {
+        *counts.entry(pos.to_geohash(precision)).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Distances in kilometres between every pair of positioned aircraft.
+///
+/// Pairs are `(i, j)` indices into `aircraft` with `i < j`; aircraft without
+/// a position are skipped. This compares every pair, so it is O(n²) in both
+/// time and output size. Use [`min_separation`] when only the closest pair
+/// is needed.
+pub fn pairwise_distances(aircraft: &[Aircraft]) -> Vec<((usize, usize), f64)> {
+    let positioned: Vec<(usize, Coordinate)> = aircraft
+        .iter()
+        .enumerate()
+        .filter_map(|(i, ac)| ac.position().map(|pos| (i, pos)))
+        .collect();
+
+    let mut distances = Vec::new();
+    for (a, &(i, pos_i)) in positioned.iter().enumerate() {
+        for &(j, pos_j) in &positioned[a + 1..] {
+            distances.push(((i, j), pos_i.distance_to(&pos_j)));
+        }
+    }
+    distances
+}
+
+/// Find the closest pair of positioned aircraft as `(i, j, km)` with `i < j`.
+///
+/// Sweeps the aircraft in latitude order and stops comparing once the
+/// latitude gap alone exceeds the best distance found, which avoids most of
+/// the pairs [`pairwise_distances`] would compute. Returns `None` when fewer
+/// than two aircraft have a position.
+pub fn min_separation(aircraft: &[Aircraft]) -> Option<(usize, usize, f64)> {
+    let mut positioned: Vec<(usize, Coordinate)> = aircraft
+        .iter()
+        .enumerate()
+        .filter_map(|(i, ac)| ac.position().map(|pos| (i, pos)))
+        .collect();
+    positioned.sort_by(|a, b| a.1.latitude.total_cmp(&b.1.latitude));
+
+    let km_per_deg_lat = EARTH_RADIUS_KM.to_radians();
+    let mut best: Option<(usize, usize, f64)> = None;
+    for (a, &(i, pos_i)) in positioned.iter().enumerate() {
+        for &(j, pos_j) in &positioned[a + 1..] {
+            let lat_gap_km = (pos_j.latitude - pos_i.latitude) * km_per_deg_lat;
+            if best.is_some_and(|(_, _, d)| lat_gap_km > d) {
+                break;
+            }
+            let d = pos_i.distance_to(&pos_j);
+            if best.is_none_or(|(_, _, best_d)| d < best_d) {
+                best = Some((i.min(j), i.max(j), d));
+            }
+        }
+    }
+    best
+}
+
+/// Half-width, in degrees, that [`bounding_box_of`] gives a box along an
+/// axis where every aircraft has the same coordinate (about 100 m).
+const MIN_BOX_HALF_SPAN_DEG: f64 = 0.001;
+
+/// Tightest bounding box containing every positioned aircraft, e.g. to fit
+/// a map to the results.
+///
+/// When all aircraft share a latitude or longitude, that side is widened
+/// by 0.001° (about 100 m) either way so the box stays valid. As with
+/// [`BoundingBox::enclosing`], traffic either side of the antimeridian gets
+/// a box spanning the rest of the globe. Returns `None` when no aircraft
+/// has a position.
+pub fn bounding_box_of(aircraft: &[Aircraft]) -> Option<BoundingBox> {
+    let positions: Vec<Coordinate> = aircraft.iter().filter_map(Aircraft::position).collect();
+    let first = positions.first()?;
+    let (mut lat_min, mut lat_max) = (first.latitude, first.latitude);
+    let (mut lon_min, mut lon_max) = (first.longitude, first.longitude);
+    for pos in &positions[1..] {
+        lat_min = lat_min.min(pos.latitude);
+        lat_max = lat_max.max(pos.latitude);
+        lon_min = lon_min.min(pos.longitude);
+        lon_max = lon_max.max(pos.longitude);
+    }
+
+    if lat_min == lat_max {
+        lat_min = (lat_min - MIN_BOX_HALF_SPAN_DEG).max(-90.0);
+        lat_max = (lat_max + MIN_BOX_HALF_SPAN_DEG).min(90.0);
+    }
+    if lon_min == lon_max {
+        lon_min = (lon_min - MIN_BOX_HALF_SPAN_DEG).max(-180.0);
+        lon_max = (lon_max + MIN_BOX_HALF_SPAN_DEG).min(180.0);
+    }
+    BoundingBox::new(lat_min, lon_min, lat_max, lon_max).ok()
+}
+
+/// Spherical mean position of the positioned aircraft.
+///
+/// Positions are averaged as 3D unit vectors and projected back onto the
+/// sphere, so points either side of the antimeridian average to a point on
+/// it rather than to the other side of the globe. Returns `None` when no
+/// aircraft has a position, or when the positions cancel out (e.g. two
+/// antipodal aircraft) and no mean direction exists.
+pub fn centroid(aircraft: &[Aircraft]) -> Option<Coordinate> {
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    for pos in aircraft.iter().filter_map(Aircraft::position) {
+        let (lat, lon) = (pos.latitude.to_radians(), pos.longitude.to_radians());
+        x += lat.cos() * lon.cos();
+        y += lat.cos() * lon.sin();
+        z += lat.sin();
+    }
+
+    let horizontal = x.hypot(y);
+    if horizontal.hypot(z) < 1e-9 {
+        return None;
+    }
+    Some(Coordinate {
+        latitude: z.atan2(horizontal).to_degrees(),
+        longitude: y.atan2(x).to_degrees(),
+    })
+}
+
+/// Smallest circle containing every positioned aircraft, as
+/// `(center, radius_km)`.
+///
+/// Positions are projected onto a flat plane around the first positioned
+/// aircraft and solved there with the incremental (Welzl) algorithm, so the
+/// result is accurate for traffic spread over a few hundred kilometres and
+/// approximate beyond that. The radius is the great-circle distance from the
+/// center to the farthest aircraft. Returns `None` when no aircraft has a
+/// position.
+pub fn enclosing_circle(aircraft: &[Aircraft]) -> Option<(Coordinate, f64)> {
+    let positions: Vec<Coordinate> = aircraft.iter().filter_map(Aircraft::position).collect();
+    let origin = *positions.first()?;
+    let points: Vec<(f64, f64)> = positions
+        .iter()
+        .map(|&pos| local_offset_km(origin, pos))
+        .collect();
+
+    let mut circle = (points[0], 0.0);
+    for i in 1..points.len() {
+        if in_circle(circle, points[i]) {
+            continue;
+        }
+        circle = (points[i], 0.0);
+        for j in 0..i {
+            if in_circle(circle, points[j]) {
+                continue;
+            }
+            circle = circle_from_two(points[i], points[j]);
+            for k in 0..j {
+                if !in_circle(circle, points[k]) {
+                    circle = circle_from_three(points[i], points[j], points[k]);
+                }
+            }
+        }
+    }
+
+    let center = local_point(origin, circle.0);
+    let radius = positions
+        .iter()
+        .map(|pos| center.distance_to(pos))
+        .fold(0.0, f64::max);
+    Some((center, radius))
+}
+
+type Circle = ((f64, f64), f64);
+
+fn in_circle((center, radius): Circle, point: (f64, f64)) -> bool {
+    (point.0 - center.0).hypot(point.1 - center.1) <= radius + 1e-9
+}
+
+/// Circle with `a` and `b` on opposite ends of a diameter.
+fn circle_from_two(a: (f64, f64), b: (f64, f64)) -> Circle {
+    let center = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+    (center, (a.0 - center.0).hypot(a.1 - center.1))
+}
+
+/// Circle through `a`, `b` and `c`, or the widest two-point circle when they
+/// are collinear.
+fn circle_from_three(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> Circle {
+    let (bx, by) = (b.0 - a.0, b.1 - a.1);
+    let (cx, cy) = (c.0 - a.0, c.1 - a.1);
+    let d = 2.0 * (bx * cy - by * cx);
+    if d.abs() < 1e-12 {
+        return [
+            circle_from_two(a, b),
+            circle_from_two(a, c),
+            circle_from_two(b, c),
+        ]
+        .into_iter()
+        .fold(
+            (a, 0.0),
+            |widest, circle| {
+                if circle.1 > widest.1 {
+                    circle
+                } else {
+                    widest
+                }
+            },
+        );
+    }
+    let b_sq = bx * bx + by * by;
+    let c_sq = cx * cx + cy * cy;
+    let ux = (cy * b_sq - by * c_sq) / d;
+    let uy = (bx * c_sq - cx * b_sq) / d;
+    ((a.0 + ux, a.1 + uy), ux.hypot(uy))
+}
+
+/// Insert great-circle points so consecutive coordinates are at most
+/// `max_gap_km` apart.
+///
+/// Original points are kept; each segment is split into equal parts. Input
+/// of fewer than two points, or a `max_gap_km` that is not positive, comes
+/// back unchanged.
+pub fn densify(coords: &[Coordinate], max_gap_km: f64) -> Vec<Coordinate> {
+    if coords.len() < 2 || max_gap_km <= 0.0 || !max_gap_km.is_finite() {
+        return coords.to_vec();
+    }
+
+    let mut dense = vec![coords[0]];
+    for pair in coords.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        let parts = (from.distance_to(&to) / max_gap_km).ceil().max(1.0) as usize;
+        for step in 1..parts {
+            dense.push(from.intermediate(&to, step as f64 / parts as f64));
+        }
+        dense.push(to);
+    }
+    dense
+}
+
+/// Cells of a `rows` x `cols` grid over `bbox` that contain no positioned
+/// aircraft, in [`BoundingBox::grid`] order.
+///
+/// A crude proxy for receiver coverage gaps: a cell can also be empty simply
+/// because nothing was flying there.
+pub fn empty_cells(
+    aircraft: &[Aircraft],
+    bbox: BoundingBox,
+    rows: usize,
+    cols: usize,
+) -> Vec<BoundingBox> {
+    let positions: Vec<Coordinate> = aircraft.iter().filter_map(Aircraft::position).collect();
+    bbox.grid(rows, cols)
+        .filter(|cell| !positions.iter().any(|&pos| cell.contains(pos)))
+        .collect()
+}
+
+/// What changed between two snapshots; see [`diff_snapshots`].
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotDiff {
+    /// Aircraft present now but not before.
+    pub entered: Vec<Aircraft>,
+    /// Aircraft present before but not now, as last seen.
+    pub exited: Vec<Aircraft>,
+    /// Aircraft present in both, in their current state.
+    pub updated: Vec<Aircraft>,
+}
+
+/// The address snapshots match aircraft by: the [`normalize_icao24`] form,
+/// or the trimmed lowercase address if it is malformed.
+fn snapshot_key(ac: &Aircraft) -> String {
+    normalize_icao24(&ac.icao24).unwrap_or_else(|_| ac.icao24.trim().to_ascii_lowercase())
+}
+
+/// Compare two snapshots by ICAO24 address.
+///
+/// Addresses are matched in their [`normalize_icao24`] form, falling back to
+/// lowercase for malformed ones. If an address appears more than
+/// once in a snapshot, its last entry is used. Each list is sorted by
+/// address so the output does not depend on input order.
+pub fn diff_snapshots(prev: &[Aircraft], curr: &[Aircraft]) -> SnapshotDiff {
+    let by_icao24 = |aircraft: &[Aircraft]| -> BTreeMap<String, Aircraft> {
+        aircraft
+            .iter()
+            .map(|ac| (snapshot_key(ac), ac.clone()))
+            .collect()
+    };
+    let mut prev = by_icao24(prev);
+    let mut diff = SnapshotDiff::default();
+
+    for (icao24, ac) in by_icao24(curr) {
+        if prev.remove(&icao24).is_some() {
+            diff.updated.push(ac);
+        } else {
+            diff.entered.push(ac);
+        }
+    }
+    diff.exited = prev.into_values().collect();
+    diff
+}
+
+/// One report per aircraft from overlapping snapshots, such as several
+/// sources or adjacent tiles, sorted by `icao24`.
+///
+/// Reports are matched by address the way [`diff_snapshots`] matches them.
+/// Of several reports of one aircraft the one with the latest
+/// `last_contact` is kept; a known time beats none, and on a tie the
+/// earliest report wins.
+pub fn dedup_freshest(aircraft: impl IntoIterator<Item = Aircraft>) -> Vec<Aircraft> {
+    let mut freshest: BTreeMap<String, Aircraft> = BTreeMap::new();
+    for ac in aircraft {
+        let key = snapshot_key(&ac);
+        match freshest.get(&key) {
+            Some(kept) if ac.last_contact <= kept.last_contact => {}
+            _ => {
+                freshest.insert(key, ac);
+            }
+        }
+    }
+    freshest.into_values().collect()
+}
+
+/// Exponential moving average of each aircraft's position across
+/// successive snapshots, to steady tracks jittered by noisy ADS-B fixes.
+///
+/// Each [`update`](Self::update) moves an aircraft's smoothed position
+/// `alpha` of the way towards its newly reported one: 1 follows the raw
+/// positions, values near 0 smooth heavily. Longitudes are averaged the
+/// short way round, so tracks crossing the antimeridian stay put.
+#[derive(Debug, Clone)]
+pub struct PositionSmoother {
+    alpha: f64,
+    positions: HashMap<String, Coordinate>,
+}
+
+impl PositionSmoother {
+    /// Create a smoother; `alpha` is clamped to `0.0..=1.0`, and NaN means
+    /// no smoothing.
+    pub fn new(alpha: f64) -> Self {
+        let alpha = if alpha.is_nan() {
+            1.0
+        } else {
+            alpha.clamp(0.0, 1.0)
+        };
+        Self {
+            alpha,
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Fold in the next snapshot.
+    ///
+    /// Aircraft seen for the first time start at their reported position,
+    /// aircraft missing from `snapshot` are forgotten, and aircraft present
+    /// without a position keep their last smoothed one.
+    pub fn update(&mut self, snapshot: &[Aircraft]) {
+        let mut next = HashMap::with_capacity(snapshot.len());
+        for ac in snapshot {
+            let previous = self.positions.get(&ac.icao24).copied();
+            let smoothed = match (previous, ac.position()) {
+                (Some(prev), Some(pos)) => self.step(prev, pos),
+                (None, Some(pos)) => pos,
+                (Some(prev), None) => prev,
+                (None, None) => continue,
+            };
+            next.insert(ac.icao24.clone(), smoothed);
+        }
+        self.positions = next;
+    }
+
+    /// Smoothed position of the aircraft with this ICAO24 address.
+    pub fn get(&self, icao24: &str) -> Option<Coordinate> {
+        self.positions.get(icao24).copied()
+    }
+
+    fn step(&self, prev: Coordinate, pos: Coordinate) -> Coordinate {
+        let dlon = (pos.longitude - prev.longitude + 540.0).rem_euclid(360.0) - 180.0;
+        let longitude = prev.longitude + self.alpha * dlon;
+        Coordinate {
+            latitude: prev.latitude + self.alpha * (pos.latitude - prev.latitude),
+            longitude: (longitude + 540.0).rem_euclid(360.0) - 180.0,
+        }
+    }
+}
+
+/// Heuristic check of whether a position history for one aircraft looks
+/// like a holding pattern.
+///
+/// True when the track keeps turning the same way, reversing its heading
+/// over and over (about one and a half laps of a racetrack or orbit), while
+/// staying within a few tens of kilometres of where it started. This is a
+/// rough guess from positions alone: a tight orbit for photography or a
+/// search pattern looks the same, and a short or sparse history may miss a
+/// real hold.
+pub fn is_holding(track: &[Coordinate]) -> bool {
+    let Some(&start) = track.first() else {
+        return false;
+    };
+    if track
+        .iter()
+        .any(|pos| pos.distance_to(&start) > HOLDING_MAX_EXTENT_KM)
+    {
+        return false;
+    }
+
+    let mut from = start;
+    let mut heading: Option<f64> = None;
+    let mut net_turn = 0.0;
+    for &pos in &track[1..] {
+        if from.distance_to(&pos) < HOLDING_MIN_LEG_KM {
+            continue;
+        }
+        let bearing = from.rhumb_bearing_to(&pos);
+        if let Some(prev) = heading {
+            net_turn += (bearing - prev + 540.0).rem_euclid(360.0) - 180.0;
+        }
+        heading = Some(bearing);
+        from = pos;
+    }
+    net_turn.abs() >= HOLDING_MIN_TURN_DEG
+}
+
+/// Aircraft roughly overhead an observer: positioned within `ground_radius_km`
+/// of `observer` and with a barometric altitude of at least `min_alt_m`.
+///
+/// Aircraft without a position or barometric altitude are skipped.
+pub fn overhead(
+    aircraft: &[Aircraft],
+    observer: Coordinate,
+    ground_radius_km: f64,
+    min_alt_m: f64,
+) -> Vec<&Aircraft> {
+    aircraft
+        .iter()
+        .filter(|ac| {
+            ac.position()
+                .is_some_and(|pos| pos.distance_to(&observer) <= ground_radius_km)
+                && ac.baro_altitude.is_some_and(|alt| alt >= min_alt_m)
+        })
+        .collect()
+}
+
+/// Positioned aircraft as a GeoJSON `FeatureCollection` of points.
+///
+/// Each feature carries the aircraft's fields, minus latitude and longitude,
+/// as properties. Aircraft without a position are left out.
+pub fn to_geojson(aircraft: &[Aircraft]) -> serde_json::Value {
+    let features: Vec<serde_json::Value> = aircraft
+        .iter()
+        .filter_map(|ac| {
+            let (lat, lon) = (ac.latitude?, ac.longitude?);
+            let properties = geojson_properties(ac)?;
+            Some(serde_json::json!({
+                "type": "Feature",
+                // GeoJSON puts longitude first.
+                "geometry": { "type": "Point", "coordinates": [lon, lat] },
+                "properties": properties,
+            }))
+        })
+        .collect();
+    serde_json::json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// Like [`to_geojson`], but aircraft without a position are kept too, as a
+/// top-level `"unpositioned"` array of their fields (minus latitude and
+/// longitude) next to `"features"`.
+///
+/// GeoJSON readers ignore unknown top-level members, so the result still
+/// loads as a plain `FeatureCollection`.
+pub fn to_geojson_with_unpositioned(aircraft: &[Aircraft]) -> serde_json::Value {
+    let mut geojson = to_geojson(aircraft);
+    let unpositioned: Vec<serde_json::Value> = aircraft
+        .iter()
+        .filter(|ac| ac.latitude.is_none() || ac.longitude.is_none())
+        .filter_map(geojson_properties)
+        .collect();
+    geojson["unpositioned"] = serde_json::Value::Array(unpositioned);
+    geojson
+}
+
+/// An aircraft's fields without latitude and longitude, which GeoJSON
+/// carries in the geometry instead.
+fn geojson_properties(ac: &Aircraft) -> Option<serde_json::Value> {
+    let mut properties = serde_json::to_value(ac).ok()?;
+    if let Some(map) = properties.as_object_mut() {
+        map.remove("latitude");
+        map.remove("longitude");
+    }
+    Some(properties)
+}
+
+/// Order aircraft by their estimated time to closest approach to
+/// `observer`, soonest first; see [`Aircraft::time_to_closest_approach`].
+///
+/// Aircraft moving away or lacking position, track or velocity go last,
+/// in their original order.
+pub fn sort_by_time_to_overhead(aircraft: &mut [Aircraft], observer: Coordinate) {
+    let eta = |ac: &Aircraft| {
+        ac.time_to_closest_approach(observer)
+            .unwrap_or(f64::INFINITY)
+    };
+    aircraft.sort_by(|a, b| eta(a).total_cmp(&eta(b)));
+}
+
+/// Which aircraft [`filter_ground`] keeps, by their `on_ground` flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroundFilter {
+    OnlyAirborne,
+    OnlyGround,
+    #[default]
+    Both,
+}
+
+/// Keep aircraft according to whether they report being on the ground.
+pub fn filter_ground(aircraft: Vec<Aircraft>, keep: GroundFilter) -> Vec<Aircraft> {
+    match keep {
+        GroundFilter::OnlyAirborne => aircraft.into_iter().filter(|ac| !ac.on_ground).collect(),
+        GroundFilter::OnlyGround => aircraft.into_iter().filter(|ac| ac.on_ground).collect(),
+        GroundFilter::Both => aircraft,
+    }
+}
+
+/// Keep aircraft whose barometric altitude lies within `min_m..=max_m`
+/// metres; a `None` bound is open. With either bound set, aircraft without a
+/// barometric altitude are dropped.
+pub fn filter_altitude(
+    aircraft: Vec<Aircraft>,
+    min_m: Option<f64>,
+    max_m: Option<f64>,
+) -> Vec<Aircraft> {
+    if min_m.is_none() && max_m.is_none() {
+        return aircraft;
+    }
+    aircraft
+        .into_iter()
+        .filter(|ac| {
+            ac.baro_altitude.is_some_and(|alt| {
+                min_m.is_none_or(|min| alt >= min) && max_m.is_none_or(|max| alt <= max)
+            })
+        })
+        .collect()
+}
+
+/// Altitude in metres of flight level `fl` (hundreds of feet).
+///
+/// Flight levels are pressure altitudes against the standard 1013.25 hPa
+/// setting, as is OpenSky's barometric altitude, so the two compare
+/// directly; neither is the true height above sea level.
+pub fn flight_level_to_m(fl: u32) -> f64 {
+    f64::from(fl) * 100.0 * METRES_PER_FOOT
+}
+
+/// [`filter_altitude`] with bounds given as flight levels, e.g. 350 for
+/// FL350 (35,000 ft).
+pub fn filter_flight_levels(
+    aircraft: Vec<Aircraft>,
+    min_fl: Option<u32>,
+    max_fl: Option<u32>,
+) -> Vec<Aircraft> {
+    filter_altitude(
+        aircraft,
+        min_fl.map(flight_level_to_m),
+        max_fl.map(flight_level_to_m),
+    )
+}
+
+/// Keep only aircraft squawking an emergency code; see
+/// [`Aircraft::is_emergency`].
+pub fn only_emergencies(aircraft: Vec<Aircraft>) -> Vec<Aircraft> {
+    aircraft
+        .into_iter()
+        .filter(Aircraft::is_emergency)
+        .collect()
+}
+
+/// Whether `point` lies inside `polygon`, by ray casting.
+///
+/// The polygon may be given open or closed (first vertex repeated at the
+/// end); the closing edge is implied either way. Vertices are treated as
+/// points on a flat lat/lon plane, so polygons crossing the antimeridian are
+/// not supported: split them at ±180° first. Points exactly on an edge may
+/// fall either side.
+pub fn point_in_polygon(point: Coordinate, polygon: &[Coordinate]) -> bool {
+    let (x, y) = (point.longitude, point.latitude);
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+    for (i, vi) in polygon.iter().enumerate() {
+        let vj = &polygon[j];
+        if (vi.latitude > y) != (vj.latitude > y)
+            && x < (vj.longitude - vi.longitude) * (y - vi.latitude) / (vj.latitude - vi.latitude)
+                + vi.longitude
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// ICAO24 address blocks allocated to each state, as `(first, last, country)`.
+///
+/// Taken from the ICAO Annex 10 Volume III allocation table. Entries are
+/// sorted and non-overlapping so lookups can binary search.
+const ICAO24_ALLOCATIONS: &[(u32, u32, &str)] = &[
+    (0x004000, 0x0043FF, "Zimbabwe"),
+    (0x006000, 0x006FFF, "Mozambique"),
+    (0x008000, 0x00FFFF, "South Africa"),
+    (0x010000, 0x017FFF, "Egypt"),
+    (0x018000, 0x01FFFF, "Libya"),
+    (0x020000, 0x027FFF, "Morocco"),
+    (0x028000, 0x02FFFF, "Tunisia"),
+    (0x030000, 0x0303FF, "Botswana"),
+    (0x032000, 0x032FFF, "Burundi"),
+    (0x034000, 0x034FFF, "Cameroon"),
+    (0x035000, 0x0353FF, "Comoros"),
+    (0x036000, 0x036FFF, "Congo"),
+    (0x038000, 0x038FFF, "Cote d'Ivoire"),
+    (0x03E000, 0x03EFFF, "Gabon"),
+    (0x040000, 0x040FFF, "Ethiopia"),
+    (0x042000, 0x042FFF, "Equatorial Guinea"),
+    (0x044000, 0x044FFF, "Ghana"),
+    (0x046000, 0x046FFF, "Guinea"),
+    (0x048000, 0x0483FF, "Guinea-Bissau"),
+    (0x04A000, 0x04A3FF, "Lesotho"),
+    (0x04C000, 0x04CFFF, "Kenya"),
+    (0x050000, 0x050FFF, "Liberia"),
+    (0x054000, 0x054FFF, "Madagascar"),
+    (0x058000, 0x058FFF, "Malawi"),
+    (0x05A000, 0x05A3FF, "Maldives"),
+    (0x05C000, 0x05CFFF, "Mali"),
+    (0x05E000, 0x05E3FF, "Mauritania"),
+    (0x060000, 0x0603FF, "Mauritius"),
+    (0x062000, 0x062FFF, "Niger"),
+    (0x064000, 0x064FFF, "Nigeria"),
+    (0x068000, 0x068FFF, "Uganda"),
+    (0x06A000, 0x06A3FF, "Qatar"),
+    (0x06C000, 0x06CFFF, "Central African Republic"),
+    (0x06E000, 0x06EFFF, "Rwanda"),
+    (0x070000, 0x070FFF, "Senegal"),
+    (0x074000, 0x0743FF, "Seychelles"),
+    (0x076000, 0x0763FF, "Sierra Leone"),
+    (0x078000, 0x078FFF, "Somalia"),
+    (0x07A000, 0x07A3FF, "Swaziland"),
+    (0x07C000, 0x07CFFF, "Sudan"),
+    (0x080000, 0x080FFF, "Tanzania"),
+    (0x084000, 0x084FFF, "Chad"),
+    (0x088000, 0x088FFF, "Togo"),
+    (0x08A000, 0x08AFFF, "Zambia"),
+    (0x08C000, 0x08CFFF, "Democratic Republic of the Congo"),
+    (0x090000, 0x090FFF, "Angola"),
+    (0x094000, 0x0943FF, "Benin"),
+    (0x096000, 0x0963FF, "Cape Verde"),
+    (0x098000, 0x0983FF, "Djibouti"),
+    (0x09A000, 0x09AFFF, "Gambia"),
+    (0x09C000, 0x09CFFF, "Burkina Faso"),
+    (0x09E000, 0x09E3FF, "Sao Tome and Principe"),
+    (0x0A0000, 0x0A7FFF, "Algeria"),
+    (0x0A8000, 0x0A8FFF, "Bahamas"),
+    (0x0AA000, 0x0AA3FF, "Barbados"),
+    (0x0AB000, 0x0AB3FF, "Belize"),
+    (0x0AC000, 0x0ACFFF, "Colombia"),
+    (0x0AE000, 0x0AEFFF, "Costa Rica"),
+    (0x0B0000, 0x0B0FFF, "Cuba"),
+    (0x0B2000, 0x0B2FFF, "El Salvador"),
+    (0x0B4000, 0x0B4FFF, "Guatemala"),
+    (0x0B6000, 0x0B6FFF, "Guyana"),
+    (0x0B8000, 0x0B8FFF, "Haiti"),
+    (0x0BA000, 0x0BAFFF, "Honduras"),
+    (0x0BC000, 0x0BC3FF, "Saint Vincent and the Grenadines"),
+    (0x0BE000, 0x0BEFFF, "Jamaica"),
+    (0x0C0000, 0x0C0FFF, "Nicaragua"),
+    (0x0C2000, 0x0C2FFF, "Panama"),
+    (0x0C4000, 0x0C4FFF, "Dominican Republic"),
+    (0x0C6000, 0x0C6FFF, "Trinidad and Tobago"),
+    (0x0C8000, 0x0C8FFF, "Suriname"),
+    (0x0CA000, 0x0CA3FF, "Antigua and Barbuda"),
+    (0x0CC000, 0x0CC3FF, "Grenada"),
+    (0x0D0000, 0x0D7FFF, "Mexico"),
+    (0x0D8000, 0x0DFFFF, "Venezuela"),
+    (0x100000, 0x1FFFFF, "Russian Federation"),
+    (0x201000, 0x2013FF, "Namibia"),
+    (0x202000, 0x2023FF, "Eritrea"),
+    (0x300000, 0x33FFFF, "Italy"),
+    (0x340000, 0x37FFFF, "Spain"),
+    (0x380000, 0x3BFFFF, "France"),
+    (0x3C0000, 0x3FFFFF, "Germany"),
+    (0x400000, 0x43FFFF, "United Kingdom"),
+    (0x440000, 0x447FFF, "Austria"),
+    (0x448000, 0x44FFFF, "Belgium"),
+    (0x450000, 0x457FFF, "Bulgaria"),
+    (0x458000, 0x45FFFF, "Denmark"),
+    (0x460000, 0x467FFF, "Finland"),
+    (0x468000, 0x46FFFF, "Greece"),
+    (0x470000, 0x477FFF, "Hungary"),
+    (0x478000, 0x47FFFF, "Norway"),
+    (0x480000, 0x487FFF, "Kingdom of the Netherlands"),
+    (0x488000, 0x48FFFF, "Poland"),
+    (0x490000, 0x497FFF, "Portugal"),
+    (0x498000, 0x49FFFF, "Czech Republic"),
+    (0x4A0000, 0x4A7FFF, "Romania"),
+    (0x4A8000, 0x4AFFFF, "Sweden"),
+    (0x4B0000, 0x4B7FFF, "Switzerland"),
+    (0x4B8000, 0x4BFFFF, "Turkey"),
+    (0x4C0000, 0x4C7FFF, "Serbia"),
+    (0x4C8000, 0x4C83FF, "Cyprus"),
+    (0x4CA000, 0x4CAFFF, "Ireland"),
+    (0x4CC000, 0x4CCFFF, "Iceland"),
+    (0x4D0000, 0x4D03FF, "Luxembourg"),
+    (0x4D2000, 0x4D23FF, "Malta"),
+    (0x4D4000, 0x4D43FF, "Monaco"),
+    (0x500000, 0x5003FF, "San Marino"),
+    (0x501000, 0x5013FF, "Albania"),
+    (0x501C00, 0x501FFF, "Croatia"),
+    (0x502C00, 0x502FFF, "Latvia"),
+    (0x503C00, 0x503FFF, "Lithuania"),
+    (0x504C00, 0x504FFF, "Republic of Moldova"),
+    (0x505C00, 0x505FFF, "Slovakia"),
+    (0x506C00, 0x506FFF, "Slovenia"),
+    (0x507C00, 0x507FFF, "Uzbekistan"),
+    (0x508000, 0x50FFFF, "Ukraine"),
+    (0x510000, 0x5103FF, "Belarus"),
+    (0x511000, 0x5113FF, "Estonia"),
+    (0x512000, 0x5123FF, "North Macedonia"),
+    (0x513000, 0x5133FF, "Bosnia and Herzegovina"),
+    (0x514000, 0x5143FF, "Georgia"),
+    (0x515000, 0x5153FF, "Tajikistan"),
+    (0x600000, 0x6003FF, "Armenia"),
+    (0x600800, 0x600BFF, "Azerbaijan"),
+    (0x601000, 0x6013FF, "Kyrgyzstan"),
+    (0x601800, 0x601BFF, "Turkmenistan"),
+    (0x680000, 0x6803FF, "Bhutan"),
+    (0x681000, 0x6813FF, "Micronesia"),
+    (0x682000, 0x6823FF, "Mongolia"),
+    (0x683000, 0x6833FF, "Kazakhstan"),
+    (0x684000, 0x6843FF, "Palau"),
+    (0x700000, 0x700FFF, "Afghanistan"),
+    (0x702000, 0x702FFF, "Bangladesh"),
+    (0x704000, 0x704FFF, "Myanmar"),
+    (0x706000, 0x706FFF, "Kuwait"),
+    (0x708000, 0x708FFF, "Laos"),
+    (0x70A000, 0x70AFFF, "Nepal"),
+    (0x70C000, 0x70C3FF, "Oman"),
+    (0x70E000, 0x70EFFF, "Cambodia"),
+    (0x710000, 0x717FFF, "Saudi Arabia"),
+    (0x718000, 0x71FFFF, "Republic of Korea"),
+    (0x720000, 0x727FFF, "Democratic People's Republic of Korea"),
+    (0x728000, 0x72FFFF, "Iraq"),
+    (0x730000, 0x737FFF, "Iran"),
+    (0x738000, 0x73FFFF, "Israel"),
+    (0x740000, 0x747FFF, "Jordan"),
+    (0x748000, 0x74FFFF, "Lebanon"),
+    (0x750000, 0x757FFF, "Malaysia"),
+    (0x758000, 0x75FFFF, "Philippines"),
+    (0x760000, 0x767FFF, "Pakistan"),
+    (0x768000, 0x76FFFF, "Singapore"),
+    (0x770000, 0x777FFF, "Sri Lanka"),
+    (0x778000, 0x77FFFF, "Syria"),
+    (0x780000, 0x7BFFFF, "China"),
+    (0x7C0000, 0x7FFFFF, "Australia"),
+    (0x800000, 0x83FFFF, "India"),
+    (0x840000, 0x87FFFF, "Japan"),
+    (0x880000, 0x887FFF, "Thailand"),
+    (0x888000, 0x88FFFF, "Viet Nam"),
+    (0x890000, 0x890FFF, "Yemen"),
+    (0x894000, 0x894FFF, "Bahrain"),
+    (0x895000, 0x8953FF, "Brunei Darussalam"),
+    (0x896000, 0x896FFF, "United Arab Emirates"),
+    (0x897000, 0x8973FF, "Solomon Islands"),
+    (0x898000, 0x898FFF, "Papua New Guinea"),
+    (0x899000, 0x8993FF, "Taiwan"),
+    (0x8A0000, 0x8A7FFF, "Indonesia"),
+    (0x900000, 0x9003FF, "Marshall Islands"),
+    (0x901000, 0x9013FF, "Cook Islands"),
+    (0x902000, 0x9023FF, "Samoa"),
+    (0xA00000, 0xAFFFFF, "United States"),
+    (0xC00000, 0xC3FFFF, "Canada"),
+    (0xC80000, 0xC87FFF, "New Zealand"),
+    (0xC88000, 0xC88FFF, "Fiji"),
+    (0xC8A000, 0xC8A3FF, "Nauru"),
+    (0xC8C000, 0xC8C3FF, "Saint Lucia"),
+    (0xC8D000, 0xC8D3FF, "Tonga"),
+    (0xC8E000, 0xC8E3FF, "Kiribati"),
+    (0xC90000, 0xC903FF, "Vanuatu"),
+    (0xE00000, 0xE3FFFF, "Argentina"),
+    (0xE40000, 0xE7FFFF, "Brazil"),
+    (0xE80000, 0xE80FFF, "Chile"),
+    (0xE84000, 0xE84FFF, "Ecuador"),
+    (0xE88000, 0xE88FFF, "Paraguay"),
+    (0xE8C000, 0xE8CFFF, "Peru"),
+    (0xE90000, 0xE90FFF, "Uruguay"),
+    (0xE94000, 0xE94FFF, "Bolivia"),
+];
+
+/// Look up the country an ICAO24 address was allocated to.
+///
+/// Returns `None` for malformed addresses and for addresses outside any
+/// allocated block.
+pub fn icao24_country(icao24: &str) -> Option<&'static str> {
+    let address = parse_icao24(icao24)?;
+
+    let idx = ICAO24_ALLOCATIONS.partition_point(|&(_, last, _)| last < address);
+    ICAO24_ALLOCATIONS
+        .get(idx)
+        .filter(|&&(first, _, _)| first <= address)
+        .map(|&(_, _, country)| country)
+}
+
+/// Canonical form of an ICAO24 address: six lowercase hex digits, with
+/// surrounding whitespace removed.
+///
+/// Fails with [`IfoError::InvalidIcao24`] for anything else.
+pub fn normalize_icao24(icao24: &str) -> Result<String> {
+    let trimmed = icao24.trim();
+    if trimmed.len() != 6 || !trimmed.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(IfoError::InvalidIcao24(icao24.to_string()));
+    }
+    Ok(trimmed.to_ascii_lowercase())
+}
+
+/// Parse a six-digit hex ICAO24 address.
+fn parse_icao24(icao24: &str) -> Option<u32> {
+    let icao24 = normalize_icao24(icao24).ok()?;
+    u32::from_str_radix(&icao24, 16).ok()
+}
+
+/// ICAO24 address blocks known to be used by military aircraft, as
+/// `(first, last)`.
+///
+/// Taken from the military ranges in dump1090-fa and tar1090, which are
+/// compiled from observation rather than any official allocation. Sorted and
+/// non-overlapping so lookups can binary search.
+const MILITARY_ICAO24_BLOCKS: &[(u32, u32)] = &[
+    (0x33FF00, 0x33FFFF), // Italy
+    (0x3AA000, 0x3AFFFF), // France
+    (0x3B7000, 0x3BFFFF), // France
+    (0x3EA000, 0x3EBFFF), // Germany
+    (0x3F4000, 0x3FBFFF), // Germany
+    (0x43C000, 0x43CFFF), // United Kingdom
+    (0x444000, 0x446FFF), // Austria
+    (0x44F000, 0x44FFFF), // Belgium
+    (0x480000, 0x480FFF), // Netherlands
+    (0x4B7000, 0x4B7FFF), // Switzerland
+    (0x4B8200, 0x4B82FF), // Turkey
+    (0x738A00, 0x738AFF), // Israel
+    (0x7CF800, 0x7CFAFF), // Australia
+    (0xADF7C8, 0xAFFFFF), // United States
+    (0xC20000, 0xC3FFFF), // Canada
+    (0xE40000, 0xE41FFF), // Brazil
+];
+
+/// ICAO airline designators of common carriers, as `(designator, name)`.
+///
+/// About three hundred passenger, cargo and business aviation operators
+/// that regularly show up in ADS-B data, taken from ICAO Doc 8585
+/// (Designators for Aircraft Operating Agencies) as of October 2026.
+/// Operators that have ceased flying are left out, since their
+/// designators are dead or reassigned. Sorted by designator so lookups
+/// can binary search.
+const AIRLINE_DESIGNATORS: &[(&str, &str)] = &[
+    ("AAL", "American Airlines"),
+    ("AAR", "Asiana Airlines"),
+    ("AAY", "Allegiant Air"),
+    ("ABD", "Air Atlanta Icelandic"),
+    ("ABL", "Air Busan"),
+    ("ABR", "ASL Airlines Ireland"),
+    ("ABX", "ABX Air"),
+    ("ABY", "Air Arabia"),
+    ("ACA", "Air Canada"),
+    ("ACI", "Aircalin"),
+    ("ADO", "Air Do"),
+    ("AEA", "Air Europa"),
+    ("AEE", "Aegean Airlines"),
+    ("AFL", "Aeroflot"),
+    ("AFR", "Air France"),
+    ("AHY", "Azerbaijan Airlines"),
+    ("AIC", "Air India"),
+    ("AIQ", "Thai AirAsia"),
+    ("AIZ", "Arkia"),
+    ("AJX", "Air Japan"),
+    ("AKJ", "Akasa Air"),
+    ("AKX", "ANA Wings"),
+    ("ALK", "SriLankan Airlines"),
+    ("AMF", "Ameriflight"),
+    ("AMX", "Aeromexico"),
+    ("ANA", "All Nippon Airways"),
+    ("ANE", "Air Nostrum"),
+    ("ANG", "Air Niugini"),
+    ("ANZ", "Air New Zealand"),
+    ("APJ", "Peach Aviation"),
+    ("ARG", "Aerolineas Argentinas"),
+    ("ASA", "Alaska Airlines"),
+    ("ASH", "Mesa Airlines"),
+    ("ASL", "Air Serbia"),
+    ("ASV", "Air Seoul"),
+    ("ATN", "Air Transport International"),
+    ("AUA", "Austrian Airlines"),
+    ("AUR", "Aurigny"),
+    ("AVA", "Avianca"),
+    ("AWI", "Air Wisconsin"),
+    ("AWQ", "Indonesia AirAsia"),
+    ("AXB", "Air India Express"),
+    ("AXM", "AirAsia"),
+    ("AZU", "Azul Brazilian Airlines"),
+    ("BAV", "Bamboo Airways"),
+    ("BAW", "British Airways"),
+    ("BBC", "Biman Bangladesh Airlines"),
+    ("BCS", "European Air Transport"),
+    ("BCY", "CityJet"),
+    ("BEL", "Brussels Airlines"),
+    ("BGA", "Airbus Transport International"),
+    ("BHS", "Bahamasair"),
+    ("BKP", "Bangkok Airways"),
+    ("BOV", "Boliviana de Aviacion"),
+    ("BOX", "AeroLogic"),
+    ("BTI", "Air Baltic"),
+    ("BTK", "Batik Air"),
+    ("BWA", "Caribbean Airlines"),
+    ("CAI", "Corendon Airlines"),
+    ("CAL", "China Airlines"),
+    ("CAO", "Air China Cargo"),
+    ("CAY", "Cayman Airways"),
+    ("CBJ", "Beijing Capital Airlines"),
+    ("CCA", "Air China"),
+    ("CDG", "Shandong Airlines"),
+    ("CEB", "Cebu Pacific"),
+    ("CES", "China Eastern Airlines"),
+    ("CFE", "BA CityFlyer"),
+    ("CFG", "Condor"),
+    ("CHB", "West Air"),
+    ("CHH", "Hainan Airlines"),
+    ("CJT", "Cargojet"),
+    ("CKK", "China Cargo Airlines"),
+    ("CKS", "Kalitta Air"),
+    ("CLH", "Lufthansa CityLine"),
+    ("CLX", "Cargolux"),
+    ("CMP", "Copa Airlines"),
+    ("CPA", "Cathay Pacific"),
+    ("CQH", "Spring Airlines"),
+    ("CRK", "Hong Kong Airlines"),
+    ("CRL", "Corsair"),
+    ("CSA", "Czech Airlines"),
+    ("CSC", "Sichuan Airlines"),
+    ("CSH", "Shanghai Airlines"),
+    ("CSN", "China Southern Airlines"),
+    ("CSZ", "Shenzhen Airlines"),
+    ("CTN", "Croatia Airlines"),
+    ("CTV", "Citilink"),
+    ("CUA", "China United Airlines"),
+    ("CUB", "Cubana"),
+    ("CXA", "Xiamen Airlines"),
+    ("CYP", "Cyprus Airways"),
+    ("CYZ", "China Postal Airlines"),
+    ("DAH", "Air Algerie"),
+    ("DAL", "Delta Air Lines"),
+    ("DHK", "DHL Air UK"),
+    ("DHX", "DHL International Aviation ME"),
+    ("DKH", "Juneyao Airlines"),
+    ("DLA", "Air Dolomiti"),
+    ("DLH", "Lufthansa"),
+    ("DTA", "TAAG Angola Airlines"),
+    ("EDV", "Endeavor Air"),
+    ("EDW", "Edelweiss Air"),
+    ("EFW", "BA Euroflyer"),
+    ("EIN", "Aer Lingus"),
+    ("EJA", "NetJets"),
+    ("EJU", "easyJet Europe"),
+    ("ELY", "El Al"),
+    ("ENT", "Enter Air"),
+    ("ENY", "Envoy Air"),
+    ("ESR", "Eastar Jet"),
+    ("ETD", "Etihad Airways"),
+    ("ETH", "Ethiopian Airlines"),
+    ("EVA", "EVA Air"),
+    ("EWG", "Eurowings"),
+    ("EXS", "Jet2"),
+    ("EZS", "easyJet Switzerland"),
+    ("EZY", "easyJet"),
+    ("FAD", "flyadeal"),
+    ("FBU", "French Bee"),
+    ("FDB", "flydubai"),
+    ("FDX", "FedEx Express"),
+    ("FFT", "Frontier Airlines"),
+    ("FIN", "Finnair"),
+    ("FJI", "Fiji Airways"),
+    ("FLE", "Flair Airlines"),
+    ("FLI", "Atlantic Airways"),
+    ("FPO", "ASL Airlines France"),
+    ("FWI", "Air Caraibes"),
+    ("GCR", "Tianjin Airlines"),
+    ("GEC", "Lufthansa Cargo"),
+    ("GFA", "Gulf Air"),
+    ("GIA", "Garuda Indonesia"),
+    ("GJS", "GoJet Airlines"),
+    ("GLO", "Gol Linhas Aereas"),
+    ("GTI", "Atlas Air"),
+    ("HAL", "Hawaiian Airlines"),
+    ("HKE", "HK Express"),
+    ("HVN", "Vietnam Airlines"),
+    ("IAW", "Iraqi Airways"),
+    ("IBB", "Binter Canarias"),
+    ("IBE", "Iberia"),
+    ("IBS", "Iberia Express"),
+    ("ICE", "Icelandair"),
+    ("ICV", "Cargolux Italia"),
+    ("IGO", "IndiGo"),
+    ("IRA", "Iran Air"),
+    ("IRM", "Mahan Air"),
+    ("ISR", "Israir"),
+    ("ITY", "ITA Airways"),
+    ("JAF", "TUI fly Belgium"),
+    ("JAL", "Japan Airlines"),
+    ("JAT", "JetSMART"),
+    ("JBU", "JetBlue"),
+    ("JIA", "PSA Airlines"),
+    ("JJA", "Jeju Air"),
+    ("JJP", "Jetstar Japan"),
+    ("JNA", "Jin Air"),
+    ("JST", "Jetstar Airways"),
+    ("JTA", "Japan Transocean Air"),
+    ("JZA", "Jazz Aviation"),
+    ("JZR", "Jazeera Airways"),
+    ("KAC", "Kuwait Airways"),
+    ("KAL", "Korean Air"),
+    ("KLC", "KLM Cityhopper"),
+    ("KLM", "KLM Royal Dutch Airlines"),
+    ("KNA", "Kunming Airlines"),
+    ("KNE", "flynas"),
+    ("KQA", "Kenya Airways"),
+    ("KZR", "Air Astana"),
+    ("LAN", "LATAM Airlines Chile"),
+    ("LBT", "Nouvelair"),
+    ("LDM", "Lauda Europe"),
+    ("LGL", "Luxair"),
+    ("LKE", "Lucky Air"),
+    ("LNE", "LATAM Airlines Ecuador"),
+    ("LNI", "Lion Air"),
+    ("LNK", "Airlink"),
+    ("LOG", "Loganair"),
+    ("LOT", "LOT Polish Airlines"),
+    ("LPE", "LATAM Airlines Peru"),
+    ("LXJ", "Flexjet"),
+    ("LZB", "Bulgaria Air"),
+    ("MAS", "Malaysia Airlines"),
+    ("MAU", "Air Mauritius"),
+    ("MAY", "Malta Air"),
+    ("MDA", "Mandarin Airlines"),
+    ("MEA", "Middle East Airlines"),
+    ("MGL", "MIAT Mongolian Airlines"),
+    ("MNB", "MNG Airlines"),
+    ("MPH", "Martinair"),
+    ("MSC", "Air Cairo"),
+    ("MSR", "EgyptAir"),
+    ("MXD", "Batik Air Malaysia"),
+    ("MXY", "Breeze Airways"),
+    ("NAX", "Norwegian Air Shuttle"),
+    ("NCR", "National Airlines"),
+    ("NIA", "Nile Air"),
+    ("NJE", "NetJets Europe"),
+    ("NKS", "Spirit Airlines"),
+    ("NOK", "Nok Air"),
+    ("NOS", "Neos"),
+    ("NOZ", "Norwegian Air Norway"),
+    ("NPT", "West Atlantic UK"),
+    ("NSZ", "Norwegian Air Sweden"),
+    ("OAL", "Olympic Air"),
+    ("OAW", "Helvetic Airways"),
+    ("OKA", "Okay Airways"),
+    ("OMA", "Oman Air"),
+    ("OMS", "SalamAir"),
+    ("PAC", "Polar Air Cargo"),
+    ("PAL", "Philippine Airlines"),
+    ("PBD", "Pobeda"),
+    ("PCO", "Pacific Coastal Airlines"),
+    ("PDT", "Piedmont Airlines"),
+    ("PGT", "Pegasus Airlines"),
+    ("PIA", "Pakistan International Airlines"),
+    ("POE", "Porter Airlines"),
+    ("QFA", "Qantas"),
+    ("QLK", "QantasLink"),
+    ("QTR", "Qatar Airways"),
+    ("QXE", "Horizon Air"),
+    ("RAM", "Royal Air Maroc"),
+    ("RBA", "Royal Brunei Airlines"),
+    ("RBG", "Air Arabia Egypt"),
+    ("RJA", "Royal Jordanian"),
+    ("ROT", "TAROM"),
+    ("ROU", "Air Canada Rouge"),
+    ("RPA", "Republic Airways"),
+    ("RUK", "Ryanair UK"),
+    ("RWD", "RwandAir"),
+    ("RXA", "Regional Express"),
+    ("RYR", "Ryanair"),
+    ("RYS", "Buzz"),
+    ("RZO", "Azores Airlines"),
+    ("SAA", "South African Airways"),
+    ("SAS", "Scandinavian Airlines"),
+    ("SAT", "SATA Air Acores"),
+    ("SBI", "S7 Airlines"),
+    ("SCO", "Scoot"),
+    ("SCX", "Sun Country Airlines"),
+    ("SDM", "Rossiya Airlines"),
+    ("SEJ", "SpiceJet"),
+    ("SFJ", "StarFlyer"),
+    ("SFR", "Safair"),
+    ("SHT", "BA Shuttle"),
+    ("SIA", "Singapore Airlines"),
+    ("SJX", "Starlux Airlines"),
+    ("SJY", "Sriwijaya Air"),
+    ("SKU", "Sky Airline"),
+    ("SKW", "SkyWest Airlines"),
+    ("SKY", "Skymark Airlines"),
+    ("SLI", "Aeromexico Connect"),
+    ("SQC", "Singapore Airlines Cargo"),
+    ("SVA", "Saudia"),
+    ("SVR", "Ural Airlines"),
+    ("SWA", "Southwest Airlines"),
+    ("SWR", "Swiss International Air Lines"),
+    ("SXS", "SunExpress"),
+    ("SZS", "SAS Connect"),
+    ("TAM", "LATAM Airlines Brasil"),
+    ("TAP", "TAP Air Portugal"),
+    ("TAR", "Tunisair"),
+    ("TAY", "ASL Airlines Belgium"),
+    ("TFL", "TUI fly Netherlands"),
+    ("TGZ", "Georgian Airways"),
+    ("THA", "Thai Airways"),
+    ("THT", "Air Tahiti Nui"),
+    ("THY", "Turkish Airlines"),
+    ("TLM", "Thai Lion Air"),
+    ("TOM", "TUI Airways"),
+    ("TRA", "Transavia"),
+    ("TSC", "Air Transat"),
+    ("TTW", "Tigerair Taiwan"),
+    ("TUI", "TUIfly"),
+    ("TVF", "Transavia France"),
+    ("TVJ", "Thai Vietjet Air"),
+    ("TVS", "Smartwings"),
+    ("TWB", "T'way Air"),
+    ("UAE", "Emirates"),
+    ("UAL", "United Airlines"),
+    ("UBG", "US-Bangla Airlines"),
+    ("UEA", "Chengdu Airlines"),
+    ("UIA", "Uni Air"),
+    ("UPS", "UPS Airlines"),
+    ("UTA", "UTair"),
+    ("UZB", "Uzbekistan Airways"),
+    ("VIR", "Virgin Atlantic"),
+    ("VIV", "Viva Aerobus"),
+    ("VJC", "VietJet Air"),
+    ("VJT", "VistaJet"),
+    ("VLG", "Vueling"),
+    ("VOE", "Volotea"),
+    ("VOI", "Volaris"),
+    ("VOZ", "Virgin Australia"),
+    ("VXP", "Avelo Airlines"),
+    ("WIF", "Wideroe"),
+    ("WJA", "WestJet"),
+    ("WMT", "Wizz Air Malta"),
+    ("WUK", "Wizz Air UK"),
+    ("WZZ", "Wizz Air"),
+    ("XAX", "AirAsia X"),
+];
+
+/// Aircraft in an area together with the time the data was valid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AreaSnapshot {
+    /// Unix timestamp the state vectors are associated with.
+    pub time: Option<i64>,
+    pub aircraft: Vec<Aircraft>,
+}
+
+impl AreaSnapshot {
+    /// [`time`](Self::time) as an RFC 3339 UTC string.
+    #[cfg(feature = "time")]
+    pub fn time_rfc3339(&self) -> Option<String> {
+        self.time.and_then(format_unix_time)
+    }
+}
+
+/// Represents a location from geocoding.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Location {
+    pub lat: f64,
+    pub lon: f64,
+    pub display_name: String,
+    /// OpenStreetMap feature class, e.g. "place" or "aeroway".
+    pub class: Option<String>,
+    /// OpenStreetMap feature type within the class, e.g. "city" or "aerodrome".
+    pub place_type: Option<String>,
+    /// Nominatim's relevance estimate from 0 to 1; low values often mean a
+    /// poor match. Photon does not report one.
+    pub importance: Option<f64>,
+}
+
+impl Location {
+    /// Whether OpenStreetMap classifies this location as a body of water,
+    /// such as an ocean, sea, bay or lake.
+    ///
+    /// OpenSky receivers are land-based, so few aircraft show up far out
+    /// over water even when there are some.
+    pub fn is_water(&self) -> bool {
+        matches!(
+            (self.class.as_deref(), self.place_type.as_deref()),
+            (Some("place"), Some("ocean" | "sea"))
+                | (Some("natural"), Some("water" | "bay" | "strait" | "sea"))
+                | (Some("water"), _)
+        )
+    }
+}
+
+/// Response from OpenSky API.
+#[derive(Debug, Deserialize)]
+pub struct OpenSkyResponse {
+    pub time: Option<i64>,
+    pub states: Option<Vec<Vec<serde_json::Value>>>,
+}
+
+/// A flight as reported by OpenSky's `/flights` endpoints.
+///
+/// Airports are OpenSky's estimates (ICAO codes) and may be missing when no
+/// airport was close enough to the first or last position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightInfo {
+    pub icao24: String,
+    #[serde(default)]
+    pub callsign: Option<String>,
+    /// Unix timestamp the aircraft was first seen on this flight.
+    pub first_seen: i64,
+    /// Unix timestamp the aircraft was last seen on this flight.
+    pub last_seen: i64,
+    #[serde(default)]
+    pub est_departure_airport: Option<String>,
+    #[serde(default)]
+    pub est_arrival_airport: Option<String>,
+}
+
+impl FlightInfo {
+    /// [`first_seen`](Self::first_seen) as an RFC 3339 UTC string.
+    #[cfg(feature = "time")]
+    pub fn first_seen_rfc3339(&self) -> Option<String> {
+        format_unix_time(self.first_seen)
+    }
+
+    /// [`last_seen`](Self::last_seen) as an RFC 3339 UTC string.
+    #[cfg(feature = "time")]
+    pub fn last_seen_rfc3339(&self) -> Option<String> {
+        format_unix_time(self.last_seen)
+    }
+}
+
+/// Response from Nominatim geocoding API.
+#[derive(Debug, Deserialize)]
+pub struct NominatimResult {
+    pub lat: String,
+    pub lon: String,
+    pub display_name: String,
+    #[serde(default)]
+    pub class: Option<String>,
+    #[serde(default, rename = "type")]
+    pub place_type: Option<String>,
+    #[serde(default)]
+    pub importance: Option<f64>,
+}
+
+/// Response from the Photon geocoding API: a GeoJSON `FeatureCollection`.
+#[derive(Debug, Deserialize)]
+pub struct PhotonResponse {
+    #[serde(default)]
+    pub features: Vec<PhotonFeature>,
+}
+
+/// One Photon match.
+#[derive(Debug, Deserialize)]
+pub struct PhotonFeature {
+    pub geometry: PhotonGeometry,
+    #[serde(default)]
+    pub properties: PhotonProperties,
+}
+
+/// Point geometry of a Photon match.
+#[derive(Debug, Deserialize)]
+pub struct PhotonGeometry {
+    /// `[longitude, latitude]`, GeoJSON order.
+    pub coordinates: [f64; 2],
+}
+
+/// Descriptive fields of a Photon match; any may be missing.
+#[derive(Debug, Default, Deserialize)]
+pub struct PhotonProperties {
+    pub name: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub country: Option<String>,
+    /// OpenStreetMap key, e.g. "place" or "aeroway".
+    pub osm_key: Option<String>,
+    /// OpenStreetMap value, e.g. "city" or "aerodrome".
+    pub osm_value: Option<String>,
+}
+
+impl PhotonFeature {
+    /// Convert to a [`Location`], validating the coordinates.
+    ///
+    /// Photon has no single display name, so one is built from the name,
+    /// city, state and country, skipping repeats ("Berlin, Germany" rather
+    /// than "Berlin, Berlin, Germany").
+    pub fn to_location(&self) -> Result<Location> {
+        let [lon, lat] = self.geometry.coordinates;
+        let coord = Coordinate::new(lat, lon)?;
+
+        let props = &self.properties;
+        let mut parts: Vec<&str> = Vec::new();
+        for part in [&props.name, &props.city, &props.state, &props.country]
+            .into_iter()
+            .flatten()
+        {
+            if !part.is_empty() && !parts.contains(&part.as_str()) {
+                parts.push(part);
+            }
+        }
+
+        Ok(Location {
+            lat: coord.latitude,
+            lon: coord.longitude,
+            display_name: parts.join(", "),
+            class: props.osm_key.clone(),
+            place_type: props.osm_value.clone(),
+            importance: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aircraft_at(icao24: &str, position: Option<(f64, f64)>) -> Aircraft {
+        let builder = Aircraft::builder(icao24);
+        match position {
+            Some((lat, lon)) => builder.position(lat, lon).build(),
+            None => builder.build(),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_minimal_aircraft() {
+        let aircraft: Aircraft = serde_json::from_str(
+            r#"{"icao24": "abc123", "origin_country": "United States", "on_ground": false}"#,
+        )
+        .unwrap();
+
+        assert_eq!(aircraft.icao24, "abc123");
+        assert_eq!(aircraft.origin_country, "United States");
+        assert!(!aircraft.on_ground);
+        assert!(aircraft.callsign.is_none());
+        assert!(aircraft.position().is_none());
+        assert!(aircraft.squawk.is_none());
+    }
+
+    #[test]
+    fn test_read_ndjson() {
+        let input = concat!(
+            r#"{"icao24": "abc123", "callsign": "BAW1", "origin_country": "United Kingdom", "on_ground": false}"#,
+            "\n\n",
+            r#"{"icao24": "def456", "origin_country": "Germany", "on_ground": true, "baro_altitude": 120.0}"#,
+            "\n",
+        );
+
+        let aircraft = read_ndjson(input.as_bytes()).unwrap();
+        assert_eq!(aircraft.len(), 2);
+        assert_eq!(aircraft[0].callsign.as_deref(), Some("BAW1"));
+        assert_eq!(aircraft[1].baro_altitude, Some(120.0));
+
+        let groups = group_by_country(aircraft);
+        assert_eq!(groups.len(), 2);
+    }
+
+    #[test]
+    fn test_read_ndjson_invalid_line() {
+        let input = "{\"icao24\": \"abc123\"}\n";
+        assert!(matches!(
+            read_ndjson(input.as_bytes()),
+            Err(IfoError::JsonError(_))
+        ));
+    }
+
+    #[test]
+    fn test_position_timestamp_and_last_contact() {
+        let state: Vec<serde_json::Value> = serde_json::from_str(
+            r#"["abc123", "UAL123  ", "United States", 1700000000, 1700000042,
+                -122.45, 37.75, 10000.0, false, 250.5, 90.0, 0.0, null, 10100.0, null, false, 0]"#,
+        )
+        .unwrap();
+
+        let aircraft = Aircraft::from_state_vector(state).unwrap();
+        assert_eq!(aircraft.position_timestamp(), Some(1700000000));
+        assert_eq!(aircraft.last_contact, Some(1700000042));
+    }
+
+    #[test]
+    fn test_sanity_check_clean() {
+        let mut ac = aircraft_at("000001", Some((0.0, 0.0)));
+        ac.baro_altitude = Some(10_000.0);
+        ac.velocity = Some(250.0);
+        ac.true_track = Some(0.0);
+        assert!(ac.sanity_check().is_empty());
+    }
+
+    #[test]
+    fn test_sanity_check_altitude() {
+        let mut ac = aircraft_at("000001", None);
+        ac.baro_altitude = Some(-9999.0);
+        ac.geo_altitude = Some(25_000.0);
+        assert_eq!(
+            ac.sanity_check(),
+            vec![
+                Anomaly::AltitudeOutOfRange(-9999.0),
+                Anomaly::AltitudeOutOfRange(25_000.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sanity_check_negative_velocity() {
+        let mut ac = aircraft_at("000001", None);
+        ac.velocity = Some(-1.0);
+        assert_eq!(ac.sanity_check(), vec![Anomaly::NegativeVelocity(-1.0)]);
+    }
+
+    #[test]
+    fn test_sanity_check_track() {
+        let mut ac = aircraft_at("000001", None);
+        ac.true_track = Some(360.0);
+        assert_eq!(ac.sanity_check(), vec![Anomaly::TrackOutOfRange(360.0)]);
+
+        ac.true_track = Some(-10.0);
+        assert_eq!(ac.sanity_check(), vec![Anomaly::TrackOutOfRange(-10.0)]);
+    }
+
+    #[test]
+    fn test_time_to_closest_approach_inbound() {
+        let observer = Coordinate::new(0.0, 0.0).unwrap();
+        let mut ac = aircraft_at("000001", Some((0.0, 1.0)));
+        ac.true_track = Some(270.0);
+        ac.velocity = Some(250.0);
+
+        let t = ac.time_to_closest_approach(observer).unwrap();
+        assert!((t - 444.8).abs() < 0.5, "got {t}");
+    }
+
+    #[test]
+    fn test_time_to_closest_approach_outbound_or_missing() {
+        let observer = Coordinate::new(0.0, 0.0).unwrap();
+        let mut ac = aircraft_at("000001", Some((0.0, 1.0)));
+        ac.true_track = Some(90.0);
+        ac.velocity = Some(250.0);
+        assert_eq!(ac.time_to_closest_approach(observer), None);
+
+        ac.velocity = None;
+        assert_eq!(ac.time_to_closest_approach(observer), None);
+    }
+
+    #[test]
+    fn test_to_geojson() {
+        let aircraft = vec![
+            Aircraft::builder("000001")
+                .callsign("UAL123")
+                .position(37.75, -122.45)
+                .build(),
+            aircraft_at("000002", None),
+        ];
+
+        let geojson = to_geojson(&aircraft);
+
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(
+            features[0]["geometry"]["coordinates"],
+            serde_json::json!([-122.45, 37.75])
+        );
+        assert_eq!(features[0]["properties"]["callsign"], "UAL123");
+        assert!(features[0]["properties"].get("latitude").is_none());
+    }
+
+    #[test]
+    fn test_to_geojson_with_unpositioned() {
+        let mut half = aircraft_at("000003", None);
+        half.latitude = Some(37.7);
+        let aircraft = vec![
+            aircraft_at("000001", Some((37.75, -122.45))),
+            aircraft_at("000002", None),
+            half,
+        ];
+
+        let geojson = to_geojson_with_unpositioned(&aircraft);
+
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0]["properties"]["icao24"], "000001");
+        let unpositioned = geojson["unpositioned"].as_array().unwrap();
+        let ids: Vec<&str> = unpositioned
+            .iter()
+            .map(|ac| ac["icao24"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, ["000002", "000003"]);
+        assert!(unpositioned[1].get("latitude").is_none());
+    }
+
+    #[test]
+    fn test_sort_by_time_to_overhead() {
+        let observer = Coordinate::new(0.0, 0.0).unwrap();
+        let mut aircraft = vec![
+            moving("000001", (0.0, 1.0), 90.0, 250.0),
+            aircraft_at("000002", Some((0.0, 0.5))),
+            // Twice as far, but three times as fast.
+            moving("000003", (0.0, -2.0), 90.0, 750.0),
+            moving("000004", (0.0, 1.0), 270.0, 250.0),
+        ];
+
+        sort_by_time_to_overhead(&mut aircraft, observer);
+
+        let order: Vec<&str> = aircraft.iter().map(|ac| ac.icao24.as_str()).collect();
+        assert_eq!(order, ["000003", "000004", "000001", "000002"]);
+    }
+
+    fn moving(icao24: &str, position: (f64, f64), track: f64, velocity: f64) -> Aircraft {
+        let mut ac = aircraft_at(icao24, Some(position));
+        ac.true_track = Some(track);
+        ac.velocity = Some(velocity);
+        ac
+    }
+
+    #[test]
+    fn test_elevation_from() {
+        let observer = Coordinate::new(51.47, -0.45).unwrap();
+        let overhead = Aircraft::builder("000001")
+            .position(51.47, -0.45)
+            .baro_altitude(10_000.0)
+            .build();
+        assert!((overhead.elevation_from(observer, 0.0).unwrap() - 90.0).abs() < 1e-9);
+
+        // 10 km up and 200 km away: 2.9° on a flat Earth, but the curvature
+        // hides about 3 km of that height.
+        let far = observer.destination(90.0, 200.0);
+        let distant = Aircraft::builder("000002")
+            .position(far.latitude, far.longitude)
+            .geo_altitude(10_000.0)
+            .build();
+        let elevation = distant.elevation_from(observer, 0.0).unwrap();
+        assert!(elevation > 1.9 && elevation < 2.0, "{}", elevation);
+
+        let unknown_alt = Aircraft::builder("000003").position(51.47, -0.45).build();
+        assert_eq!(unknown_alt.elevation_from(observer, 0.0), None);
+    }
+
+    #[test]
+    fn test_separation_3d() {
+        // 0.027 degrees of latitude is about 3 km.
+        let low = Aircraft::builder("000001")
+            .position(50.0, 0.0)
+            .baro_altitude(3000.0)
+            .build();
+        let high = Aircraft::builder("000002")
+            .position(50.0 + 3.0 / EARTH_RADIUS_KM.to_radians(), 0.0)
+            .baro_altitude(7000.0)
+            .build();
+
+        let sep = low.separation_3d(&high).unwrap();
+        assert!((sep.horizontal_km - 3.0).abs() < 1e-9);
+        assert_eq!(sep.vertical_m, 4000.0);
+        assert!((sep.slant_m - 5000.0).abs() < 1e-6);
+        assert_eq!(high.separation_3d(&low), Some(sep));
+
+        let unknown_alt = aircraft_at("000003", Some((50.0, 0.0)));
+        assert!(low.separation_3d(&unknown_alt).is_none());
+        assert!(aircraft_at("000004", None).separation_3d(&low).is_none());
+    }
+
+    #[test]
+    fn test_is_converging_with() {
+        let west = moving("000001", (50.0, 0.0), 90.0, 200.0);
+        let east = moving("000002", (50.0, 1.0), 270.0, 200.0);
+        assert_eq!(west.is_converging_with(&east), Some(true));
+        assert_eq!(east.is_converging_with(&west), Some(true));
+
+        let diverging = moving("000003", (50.0, 1.0), 90.0, 250.0);
+        assert_eq!(west.is_converging_with(&diverging), Some(false));
+
+        // Same direction, but the trailing aircraft is faster
+        let trailing = moving("000004", (50.0, -1.0), 90.0, 250.0);
+        assert_eq!(trailing.is_converging_with(&west), Some(true));
+
+        let unknown = aircraft_at("000005", Some((50.0, 2.0)));
+        assert_eq!(west.is_converging_with(&unknown), None);
+    }
+
+    #[test]
+    fn test_group_by_country() {
+        let mut aircraft = Vec::new();
+        for (icao24, callsign, country) in [
+            ("000001", Some("DLH400"), "Germany"),
+            ("000002", None, "United States"),
+            ("000003", Some("UAL1"), "United States"),
+            ("000004", Some("XYZ1"), ""),
+            ("000005", Some("AAL100"), "United States"),
+        ] {
+            let mut ac = aircraft_at(icao24, None);
+            ac.callsign = callsign.map(str::to_string);
+            ac.origin_country = country.to_string();
+            aircraft.push(ac);
+        }
+
+        let groups = group_by_country(aircraft);
+        let countries: Vec<&str> = groups.keys().map(String::as_str).collect();
+        assert_eq!(countries, vec!["(unknown)", "Germany", "United States"]);
+
+        let us: Vec<&str> = groups["United States"]
+            .iter()
+            .map(|ac| ac.icao24.as_str())
+            .collect();
+        assert_eq!(us, vec!["000005", "000003", "000002"]);
+    }
+
+    #[test]
+    fn test_coordinate_parse() {
+        let coord = Coordinate::parse(" 37.7 , -122.4 ").unwrap();
+        assert_eq!(coord, Coordinate::new(37.7, -122.4).unwrap());
+
+        assert!(matches!(
+            Coordinate::parse("37.7"),
+            Err(IfoError::InvalidCoordinates(_))
+        ));
+        assert!(matches!(
+            Coordinate::parse("London, UK"),
+            Err(IfoError::InvalidCoordinates(_))
+        ));
+        assert!(matches!(
+            Coordinate::parse("95.0,0.0"),
+            Err(IfoError::InvalidLatitude(_))
+        ));
+    }
+
+    #[test]
+    fn test_bounding_box_parse() {
+        let bbox = BoundingBox::parse("51.3, -0.5, 51.7, 0.3").unwrap();
+        assert_eq!(bbox, BoundingBox::new(51.3, -0.5, 51.7, 0.3).unwrap());
+        assert_eq!(bbox.center(), Coordinate::new(51.5, -0.1).unwrap());
+
+        assert!(matches!(
+            BoundingBox::parse("51.3,-0.5,51.7"),
+            Err(IfoError::InvalidBoundingBox(_))
+        ));
+        assert!(matches!(
+            BoundingBox::parse("51.3,west,51.7,0.3"),
+            Err(IfoError::InvalidBoundingBox(_))
+        ));
+        assert!(matches!(
+            BoundingBox::parse("51.7,-0.5,51.3,0.3"),
+            Err(IfoError::InvalidBoundingBox(_))
+        ));
+    }
+
+    #[test]
+    fn test_bounding_box_tiles() {
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        let tiles = bbox.tiles(4.0).unwrap();
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(
+            tiles[0],
+            BoundingBox::new(40.0, 0.0, 42.5, 10.0 / 3.0).unwrap()
+        );
+        assert_eq!(tiles[5].lat_max, 45.0);
+        assert_eq!(tiles[5].lon_max, 10.0);
+        assert!(tiles
+            .iter()
+            .all(|t| t.lat_max - t.lat_min <= 4.0 && t.lon_max - t.lon_min <= 4.0));
+
+        assert_eq!(bbox.tiles(20.0).unwrap(), vec![bbox]);
+        assert!(matches!(
+            bbox.tiles(0.0),
+            Err(IfoError::InvalidBoundingBox(_))
+        ));
+
+        // Same cells as grid(), with the outer edges left exact.
+        let awkward = BoundingBox::new(0.1, 0.1, 0.7, 0.7).unwrap();
+        let tiles = awkward.tiles(0.1).unwrap();
+        assert_eq!(tiles, awkward.grid(6, 6).collect::<Vec<_>>());
+        assert_eq!(tiles.last().unwrap().lat_max, 0.7);
+        assert_eq!(tiles.last().unwrap().lon_max, 0.7);
+
+        // A tiny span over a continent is refused rather than allocated.
+        let continent = BoundingBox::new(-60.0, -180.0, 80.0, 180.0).unwrap();
+        assert!(matches!(
+            continent.tiles(1e-9),
+            Err(IfoError::InvalidBoundingBox(_))
+        ));
+    }
+
+    #[test]
+    fn test_antipode() {
+        let origin = Coordinate::new(0.0, 0.0).unwrap();
+        assert_eq!(origin.antipode(), Coordinate::new(0.0, 180.0).unwrap());
+
+        let coord = Coordinate::new(45.0, 90.0).unwrap();
+        assert_eq!(coord.antipode(), Coordinate::new(-45.0, -90.0).unwrap());
+
+        let date_line = Coordinate::new(10.0, -180.0).unwrap();
+        assert_eq!(date_line.antipode(), Coordinate::new(-10.0, 0.0).unwrap());
+
+        let sf = Coordinate::new(37.7749, -122.4194).unwrap();
+        assert!(
+            (sf.distance_to(&sf.antipode()) - std::f64::consts::PI * EARTH_RADIUS_KM).abs() < 1e-6
+        );
+    }
+
+    #[test]
+    fn test_bounding_box_from_center_km() {
+        let equator = Coordinate::new(0.0, 0.0).unwrap();
+        let bbox = BoundingBox::from_center_km(equator, 111.19);
+        assert!((bbox.lat_max - 1.0).abs() < 1e-3);
+        assert!((bbox.lon_max - 1.0).abs() < 1e-3);
+
+        let north = Coordinate::new(60.0, 10.0).unwrap();
+        let bbox = BoundingBox::from_center_km(north, 111.19);
+        assert!((bbox.lat_max - 61.0).abs() < 1e-3);
+        assert!((bbox.lon_max - 12.0).abs() < 1e-3);
+
+        let nm = BoundingBox::from_center_nm(equator, 60.0);
+        assert!((nm.lat_max - 0.9994).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_bounding_box_expanded() {
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        assert_eq!(
+            bbox.expanded(1.0).unwrap(),
+            BoundingBox::new(39.0, -1.0, 46.0, 11.0).unwrap()
+        );
+        assert_eq!(
+            bbox.expanded(-2.0).unwrap(),
+            BoundingBox::new(42.0, 2.0, 43.0, 8.0).unwrap()
+        );
+
+        let polar = BoundingBox::new(89.0, 179.0, 89.5, 179.5).unwrap();
+        assert_eq!(
+            polar.expanded(1.0).unwrap(),
+            BoundingBox::new(88.0, 178.0, 90.0, 180.0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_bounding_box_over_shrink() {
+        let bbox = BoundingBox::new(40.0, 0.0, 45.0, 10.0).unwrap();
+        assert!(matches!(
+            bbox.expanded(-2.5),
+            Err(IfoError::InvalidBoundingBox(_))
+        ));
+        assert!(matches!(
+            bbox.expanded(-10.0),
+            Err(IfoError::InvalidBoundingBox(_))
+        ));
+    }
+
+    #[test]
+    fn test_to_geohash() {
+        let coord = Coordinate::new(57.64911, 10.40744).unwrap();
+        assert_eq!(coord.to_geohash(11), "u4pruydqqvj");
+        assert_eq!(coord.to_geohash(5), "u4pru");
+
+        let sf = Coordinate::new(37.7749, -122.4194).unwrap();
+        assert_eq!(sf.to_geohash(6), "9q8yyk");
+
+        let south_west = Coordinate::new(-90.0, -180.0).unwrap();
+        assert_eq!(south_west.to_geohash(3), "000");
+        assert_eq!(sf.to_geohash(0), "");
+    }
+
+    #[test]
+    fn test_geohash_density() {
+        let aircraft = vec![
+            aircraft_at("000001", Some((57.649, 10.407))),
+            aircraft_at("000002", Some((57.650, 10.408))),
+            aircraft_at("000003", Some((37.7749, -122.4194))),
+            aircraft_at("000004", None),
+        ];
+
+        let density = geohash_density(&aircraft, 4);
+        assert_eq!(density.len(), 2);
+        assert_eq!(density["u4pr"], 2);
+        assert_eq!(density["9q8y"], 1);
+    }
+
+    #[test]
+    fn test_deserialize_nominatim_class_and_type() {
+        let result: NominatimResult = serde_json::from_str(
+            r#"{"lat": "51.4706", "lon": "-0.4619", "display_name": "Heathrow Airport",
+                "class": "aeroway", "type": "aerodrome", "importance": 0.6}"#,
+        )
+        .unwrap();
+        assert_eq!(result.class.as_deref(), Some("aeroway"));
+        assert_eq!(result.place_type.as_deref(), Some("aerodrome"));
+        assert_eq!(result.importance, Some(0.6));
+
+        let bare: NominatimResult =
+            serde_json::from_str(r#"{"lat": "1", "lon": "2", "display_name": "Somewhere"}"#)
+                .unwrap();
+        assert!(bare.class.is_none());
+        assert!(bare.place_type.is_none());
+        assert!(bare.importance.is_none());
+    }
+
+    #[test]
+    fn test_distance_unit_from_km() {
+        assert_eq!(DistanceUnit::Kilometers.from_km(1.852), 1.852);
+        assert_eq!(DistanceUnit::Meters.from_km(1.852), 1852.0);
+        assert!((DistanceUnit::NauticalMiles.from_km(1.852) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_speed_conversions() {
+        let cruise = Speed::from_knots(450.0);
+        assert!((cruise.mps() - 231.5).abs() < 1e-9);
+        assert!((cruise.kmh() - 833.4).abs() < 1e-9);
+        assert!((Speed::from_kmh(cruise.kmh()).knots() - 450.0).abs() < 1e-9);
+        assert!((Speed::from_mph(cruise.mph()).mps() - cruise.mps()).abs() < 1e-9);
+        assert!((Speed::from_mph(60.0).kmh() - 96.56064).abs() < 1e-9);
+
+        assert_eq!(
+            serde_json::to_string(&Speed::from_mps(250.5)).unwrap(),
+            "250.5"
+        );
+        let ac = Aircraft::builder("000001").velocity(100.0).build();
+        assert_eq!(ac.speed(), Some(Speed::from_mps(100.0)));
+    }
+
+    #[test]
+    fn test_ground_speed_mirrors_velocity() {
+        let ac = Aircraft::builder("000001").velocity(231.5).build();
+        assert_eq!(ac.ground_speed_mps(), ac.velocity);
+        assert!((ac.ground_speed_knots().unwrap() - 450.0).abs() < 1e-9);
+        assert!((ac.ground_speed_kmh().unwrap() - 833.4).abs() < 1e-9);
+
+        let unknown = Aircraft::builder("000002").build();
+        assert_eq!(unknown.ground_speed_mps(), None);
+        assert_eq!(unknown.ground_speed_knots(), None);
+    }
+
+    #[test]
+    fn test_eta_to() {
+        let ac = Aircraft::builder("000001")
+            .position(0.0, 0.0)
+            .velocity(250.0)
+            .build();
+        let dest = Coordinate::new(0.0, 0.0).unwrap().destination(90.0, 450.0);
+        assert!((ac.eta_to(dest).unwrap() - 1800.0).abs() < 1e-6);
+
+        let parked = Aircraft::builder("000002")
+            .position(0.0, 0.0)
+            .velocity(0.2)
+            .build();
+        assert_eq!(parked.eta_to(dest), None);
+        let no_position = Aircraft::builder("000003").velocity(250.0).build();
+        assert_eq!(no_position.eta_to(dest), None);
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn test_rfc3339_timestamps() {
+        assert_eq!(
+            format_unix_time(1_700_000_000).as_deref(),
+            Some("2023-11-14T22:13:20Z")
+        );
+        assert_eq!(format_unix_time(0).as_deref(), Some("1970-01-01T00:00:00Z"));
+        assert_eq!(format_unix_time(-1), None);
+        assert_eq!(
+            format_unix_time(253_402_300_799).as_deref(),
+            Some("9999-12-31T23:59:59Z")
+        );
+        assert_eq!(format_unix_time(253_402_300_800), None);
+        assert_eq!(format_unix_time(i64::MAX), None);
+
+        let mut ac = Aircraft::builder("000001").build();
+        assert_eq!(ac.last_contact_rfc3339(), None);
+        ac.last_contact = Some(1_700_000_000);
+        assert_eq!(
+            ac.last_contact_rfc3339().as_deref(),
+            Some("2023-11-14T22:13:20Z")
+        );
+    }
+
+    #[test]
+    fn test_distance_to() {
+        let london = Coordinate::new(51.5074, -0.1278).unwrap();
+        let paris = Coordinate::new(48.8566, 2.3522).unwrap();
+        assert!((london.distance_to(&paris) - 343.5).abs() < 1.0);
+        assert_eq!(london.distance_to(&london), 0.0);
+    }
+
+    #[test]
+    fn test_destination() {
+        let origin = Coordinate::new(0.0, 0.0).unwrap();
+        let east = origin.destination(90.0, 111.0);
+        assert!(east.latitude.abs() < 1e-9);
+        assert!((east.longitude - 1.0).abs() < 0.01);
+
+        let north = origin.destination(0.0, EARTH_RADIUS_KM.to_radians() * 10.0);
+        assert!((north.latitude - 10.0).abs() < 1e-9);
+        assert!(north.longitude.abs() < 1e-9);
+
+        // Crossing the antimeridian wraps into the western hemisphere.
+        let date_line = Coordinate::new(0.0, 179.5).unwrap();
+        let wrapped = date_line.destination(90.0, EARTH_RADIUS_KM.to_radians());
+        assert!((wrapped.longitude + 179.5).abs() < 1e-9);
+
+        let sf = Coordinate::new(37.7749, -122.4194).unwrap();
+        let there = sf.destination(45.0, 500.0);
+        assert!((sf.distance_to(&there) - 500.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_intermediate() {
+        let a = Coordinate::new(0.0, 0.0).unwrap();
+        let b = Coordinate::new(0.0, 10.0).unwrap();
+        let mid = a.intermediate(&b, 0.5);
+        assert!(mid.latitude.abs() < 1e-9);
+        assert!((mid.longitude - 5.0).abs() < 1e-9);
+        assert_eq!(a.intermediate(&a, 0.5), a);
+    }
+
+    #[test]
+    fn test_great_circle_path() {
+        let london = Coordinate::new(51.47, -0.45).unwrap();
+        let new_york = Coordinate::new(40.64, -73.78).unwrap();
+
+        let path = london.great_circle_path(&new_york, 2);
+        assert_eq!(path.len(), 3);
+        assert_eq!(path[0], london);
+        assert_eq!(path[1], london.intermediate(&new_york, 0.5));
+        assert_eq!(path[2], new_york);
+
+        assert_eq!(
+            london.great_circle_path(&new_york, 0),
+            vec![london, new_york]
+        );
+        assert_eq!(london.great_circle_path(&london, 3), vec![london; 4]);
+    }
+
+    #[test]
+    fn test_densify() {
+        // Ten degrees along the equator is about 1112 km: 100 km gaps need 12
+        // equal parts, so 11 points are inserted.
+        let a = Coordinate::new(0.0, 0.0).unwrap();
+        let b = Coordinate::new(0.0, 10.0).unwrap();
+        let dense = densify(&[a, b], 100.0);
+        assert_eq!(dense.len(), 13);
+        assert_eq!(dense[0], a);
+        assert_eq!(dense[12], b);
+        assert!(dense
+            .windows(2)
+            .all(|w| w[0].distance_to(&w[1]) <= 100.0 + 1e-9));
+
+        // Already dense and single-point inputs are unchanged.
+        assert_eq!(densify(&[a, b], 2000.0), vec![a, b]);
+        assert_eq!(densify(&[a], 1.0), vec![a]);
+        assert!(densify(&[], 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_rhumb_east_west_at_high_latitude() {
+        let a = Coordinate::new(60.0, 0.0).unwrap();
+        let b = Coordinate::new(60.0, 30.0).unwrap();
+
+        // Along the parallel: 30 degrees of longitude shrunk by cos(60°).
+        let expected = 0.5 * 30f64.to_radians() * EARTH_RADIUS_KM;
+        assert!((a.rhumb_distance_to(&b) - expected).abs() < 1e-6);
+        assert!(a.rhumb_distance_to(&b) > a.distance_to(&b) + 10.0);
+        assert!((a.rhumb_bearing_to(&b) - 90.0).abs() < 1e-9);
+        assert!((b.rhumb_bearing_to(&a) - 270.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rhumb_matches_great_circle_on_equator_and_meridian() {
+        let a = Coordinate::new(0.0, 10.0).unwrap();
+        let b = Coordinate::new(0.0, 40.0).unwrap();
+        assert!((a.rhumb_distance_to(&b) - a.distance_to(&b)).abs() < 1e-6);
+
+        let north = Coordinate::new(50.0, 10.0).unwrap();
+        assert!((a.rhumb_distance_to(&north) - a.distance_to(&north)).abs() < 1e-6);
+        assert_eq!(a.rhumb_bearing_to(&north), 0.0);
+        assert_eq!(north.rhumb_bearing_to(&a), 180.0);
+    }
+
+    #[test]
+    fn test_rhumb_crosses_date_line() {
+        let west = Coordinate::new(60.0, 179.0).unwrap();
+        let east = Coordinate::new(60.0, -179.0).unwrap();
+        let expected = 0.5 * 2f64.to_radians() * EARTH_RADIUS_KM;
+        assert!((west.rhumb_distance_to(&east) - expected).abs() < 1e-6);
+        assert!((west.rhumb_bearing_to(&east) - 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pairwise_distances() {
+        let aircraft = vec![
+            aircraft_at("000001", Some((0.0, 0.0))),
+            aircraft_at("000002", None),
+            aircraft_at("000003", Some((0.0, 1.0))),
+            aircraft_at("000004", Some((0.0, 3.0))),
+        ];
+
+        let distances = pairwise_distances(&aircraft);
+        let pairs: Vec<(usize, usize)> = distances.iter().map(|&(pair, _)| pair).collect();
+        assert_eq!(pairs, vec![(0, 2), (0, 3), (2, 3)]);
+        assert!((distances[0].1 - 111.19).abs() < 0.01);
+        assert!((distances[2].1 - 222.39).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_min_separation() {
+        let aircraft = vec![
+            aircraft_at("000001", Some((10.0, 0.0))),
+            aircraft_at("000002", Some((0.0, 0.0))),
+            aircraft_at("000003", None),
+            aircraft_at("000004", Some((0.0, 0.5))),
+            aircraft_at("000005", Some((5.0, 0.0))),
+        ];
+
+        let (i, j, d) = min_separation(&aircraft).unwrap();
+        assert_eq!((i, j), (1, 3));
+        assert!((d - 55.6).abs() < 0.1);
+
+        assert!(min_separation(&aircraft[..3]).is_some());
+        assert!(min_separation(&aircraft[2..4]).is_none());
+    }
+
+    #[test]
+    fn test_bounding_box_of() {
+        let aircraft = vec![
+            aircraft_at("000001", Some((51.2, -0.5))),
+            aircraft_at("000002", Some((51.7, 0.3))),
+            aircraft_at("000003", Some((51.4, -0.1))),
+            aircraft_at("000004", None),
+        ];
+        assert_eq!(
+            bounding_box_of(&aircraft),
+            Some(BoundingBox::new(51.2, -0.5, 51.7, 0.3).unwrap())
+        );
+
+        // A single aircraft gets a small box around it.
+        let single = bounding_box_of(&aircraft[..1]).unwrap();
+        assert!((single.lat_max - single.lat_min - 0.002).abs() < 1e-9);
+        assert_eq!(single.center(), Coordinate::new(51.2, -0.5).unwrap());
+
+        let at_pole = vec![aircraft_at("000001", Some((90.0, 180.0)))];
+        let pole = bounding_box_of(&at_pole).unwrap();
+        assert_eq!((pole.lat_max, pole.lon_max), (90.0, 180.0));
+
+        assert_eq!(bounding_box_of(&aircraft[3..]), None);
+    }
+
+    #[test]
+    fn test_centroid() {
+        // Either side of the date line: the mean is on it, not at 0°.
+        let aircraft = vec![
+            aircraft_at("000001", Some((10.0, 179.0))),
+            aircraft_at("000002", Some((10.0, -179.0))),
+            aircraft_at("000003", None),
+        ];
+        let center = centroid(&aircraft).unwrap();
+        assert!((center.latitude - 10.0).abs() < 1e-2);
+        assert!((center.longitude.abs() - 180.0).abs() < 1e-9);
+
+        let single = centroid(&aircraft[..1]).unwrap();
+        assert!((single.latitude - 10.0).abs() < 1e-9);
+        assert!((single.longitude - 179.0).abs() < 1e-9);
+
+        let antipodal = vec![
+            aircraft_at("000001", Some((0.0, 0.0))),
+            aircraft_at("000002", Some((0.0, 180.0))),
+        ];
+        assert_eq!(centroid(&antipodal), None);
+        assert_eq!(centroid(&aircraft[2..]), None);
+        assert_eq!(centroid(&[]), None);
+    }
+
+    #[test]
+    fn test_enclosing_circle() {
+        // Three points on the equator one degree apart, plus one between
+        // them and one without a position: the outer two span the diameter.
+        let aircraft = vec![
+            aircraft_at("000001", Some((0.0, 0.0))),
+            aircraft_at("000002", Some((0.0, 2.0))),
+            aircraft_at("000003", Some((0.5, 1.0))),
+            aircraft_at("000004", None),
+        ];
+        let (center, radius) = enclosing_circle(&aircraft).unwrap();
+        assert!(center.latitude.abs() < 1e-9);
+        assert!((center.longitude - 1.0).abs() < 1e-9);
+        let one_degree_km = EARTH_RADIUS_KM.to_radians();
+        assert!((radius - one_degree_km).abs() < 1e-6);
+
+        // A right triangle: the hypotenuse is the diameter.
+        let triangle = vec![
+            aircraft_at("000001", Some((0.0, 0.0))),
+            aircraft_at("000002", Some((0.0, 0.2))),
+            aircraft_at("000003", Some((0.2, 0.0))),
+        ];
+        let (center, _) = enclosing_circle(&triangle).unwrap();
+        assert!((center.latitude - 0.1).abs() < 1e-3);
+        assert!((center.longitude - 0.1).abs() < 1e-3);
+
+        let (center, radius) = enclosing_circle(&aircraft[..1]).unwrap();
+        assert_eq!(center, Coordinate::new(0.0, 0.0).unwrap());
+        assert_eq!(radius, 0.0);
+
+        assert!(enclosing_circle(&aircraft[3..]).is_none());
+        assert!(enclosing_circle(&[]).is_none());
+    }
+
+    #[test]
+    fn test_empty_cells() {
+        let bbox = BoundingBox::new(0.0, 0.0, 2.0, 2.0).unwrap();
+        let aircraft = vec![
+            aircraft_at("000001", Some((0.5, 0.5))),
+            aircraft_at("000002", Some((0.5, 1.5))),
+            aircraft_at("000003", Some((1.5, 0.5))),
+            aircraft_at("000004", None),
+        ];
+
+        assert_eq!(
+            empty_cells(&aircraft, bbox, 2, 2),
+            vec![BoundingBox::new(1.0, 1.0, 2.0, 2.0).unwrap()]
+        );
+        assert_eq!(empty_cells(&[], bbox, 2, 3).len(), 6);
+        assert!(empty_cells(&aircraft, bbox, 0, 2).is_empty());
+    }
+
+    #[test]
+    fn test_position_smoother() {
+        let mut smoother = PositionSmoother::new(0.25);
+        smoother.update(&[aircraft_at("000001", Some((50.0, 8.0)))]);
+        assert_eq!(smoother.get("000001"), Coordinate::new(50.0, 8.0).ok());
+
+        smoother.update(&[
+            aircraft_at("000001", Some((50.4, 8.8))),
+            aircraft_at("000002", Some((10.0, 179.9))),
+        ]);
+        let smoothed = smoother.get("000001").unwrap();
+        assert!((smoothed.latitude - 50.1).abs() < 1e-9);
+        assert!((smoothed.longitude - 8.2).abs() < 1e-9);
+
+        // Across the antimeridian, and 000001 has left.
+        smoother.update(&[aircraft_at("000002", Some((10.0, -179.9)))]);
+        let crossing = smoother.get("000002").unwrap();
+        assert!((crossing.longitude - 179.95).abs() < 1e-9);
+        assert_eq!(smoother.get("000001"), None);
+    }
+
+    #[test]
+    fn test_is_holding() {
+        // A racetrack: 3 km legs joined by right-hand 180° turns in 30° steps.
+        let mut headings = Vec::new();
+        for _ in 0..2 {
+            headings.extend([0.0; 3]);
+            headings.extend((1..=6).map(|i| f64::from(i) * 30.0));
+            headings.extend([180.0; 3]);
+            headings.extend((7..=12).map(|i| f64::from(i) * 30.0));
+        }
+        let mut racetrack = vec![Coordinate::new(51.5, -0.4).unwrap()];
+        for heading in headings {
+            let last = *racetrack.last().unwrap();
+            racetrack.push(last.destination(heading, 1.0));
+        }
+        assert!(is_holding(&racetrack));
+
+        // One lap is not yet a hold.
+        assert!(!is_holding(&racetrack[..19]));
+
+        let start = Coordinate::new(51.5, -0.4).unwrap();
+        let straight: Vec<Coordinate> = (0..40)
+            .map(|i| start.destination(90.0, f64::from(i)))
+            .collect();
+        assert!(!is_holding(&straight));
+        assert!(!is_holding(&[]));
+    }
+
+    #[test]
+    fn test_dedup_freshest() {
+        let report = |icao24: &str, last_contact: Option<i64>, callsign: &str| {
+            let mut ac = Aircraft::builder(icao24).callsign(callsign).build();
+            ac.last_contact = last_contact;
+            ac
+        };
+        let reports = vec![
+            report("00000c", Some(100), "C1"),
+            report("00000A", Some(100), "A1"),
+            report("00000b", None, "B1"),
+            report("00000a", Some(200), "A2"),
+            report("00000b", Some(50), "B2"),
+            report("00000c", Some(100), "C2"),
+        ];
+
+        let merged = dedup_freshest(reports.clone());
+        let callsigns: Vec<&str> = merged
+            .iter()
+            .map(|ac| ac.callsign.as_deref().unwrap())
+            .collect();
+        assert_eq!(callsigns, ["A2", "B2", "C1"]);
+
+        // Same inputs, same order, whatever order they arrive in.
+        let mut reversed = reports;
+        reversed.reverse();
+        let again: Vec<String> = dedup_freshest(reversed)
+            .into_iter()
+            .map(|ac| ac.icao24)
+            .collect();
+        let first: Vec<String> = merged.into_iter().map(|ac| ac.icao24).collect();
+        assert_eq!(first, again);
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let prev = vec![
+            aircraft_at("000002", Some((50.0, 0.0))),
+            aircraft_at("000001", Some((51.0, 0.0))),
+        ];
+        let curr = vec![
+            aircraft_at("000003", Some((52.0, 0.0))),
+            aircraft_at("000002", Some((50.1, 0.0))),
+            aircraft_at("000003", Some((52.1, 0.0))),
+        ];
+
+        let diff = diff_snapshots(&prev, &curr);
+        let icao24s = |list: &[Aircraft]| -> Vec<String> {
+            list.iter().map(|ac| ac.icao24.clone()).collect()
+        };
+        assert_eq!(icao24s(&diff.entered), vec!["000003"]);
+        assert_eq!(diff.entered[0].latitude, Some(52.1));
+        assert_eq!(icao24s(&diff.exited), vec!["000001"]);
+        assert_eq!(icao24s(&diff.updated), vec!["000002"]);
+        assert_eq!(diff.updated[0].latitude, Some(50.1));
+    }
+
+    #[test]
+    fn test_overhead() {
+        let observer = Coordinate::new(51.5, -0.1).unwrap();
+        let mut above = aircraft_at("000001", Some((51.51, -0.1)));
+        above.baro_altitude = Some(3000.0);
+        let mut low = aircraft_at("000002", Some((51.5, -0.11)));
+        low.baro_altitude = Some(200.0);
+        let mut far = aircraft_at("000003", Some((52.0, -0.1)));
+        far.baro_altitude = Some(3000.0);
+        let aircraft = vec![above, low, far, aircraft_at("000004", Some((51.5, -0.1)))];
+
+        let found: Vec<&str> = overhead(&aircraft, observer, 5.0, 1000.0)
+            .iter()
+            .map(|ac| ac.icao24.as_str())
+            .collect();
+        assert_eq!(found, vec!["000001"]);
+    }
+
+    #[test]
+    fn test_point_in_polygon_triangle() {
+        let triangle = [
+            Coordinate::new(0.0, 0.0).unwrap(),
+            Coordinate::new(0.0, 10.0).unwrap(),
+            Coordinate::new(10.0, 0.0).unwrap(),
+        ];
+        let inside = Coordinate::new(2.0, 2.0).unwrap();
+        let outside = Coordinate::new(6.0, 6.0).unwrap();
+        let beyond = Coordinate::new(-1.0, 5.0).unwrap();
+
+        assert!(point_in_polygon(inside, &triangle));
+        assert!(!point_in_polygon(outside, &triangle));
+        assert!(!point_in_polygon(beyond, &triangle));
+
+        let closed = [triangle[0], triangle[1], triangle[2], triangle[0]];
+        assert!(point_in_polygon(inside, &closed));
+        assert!(!point_in_polygon(outside, &closed));
+        assert!(!point_in_polygon(inside, &[]));
+    }
+
+    #[test]
+    fn test_bounding_box_enclosing() {
+        let triangle = [
+            Coordinate::new(1.0, 2.0).unwrap(),
+            Coordinate::new(-3.0, 10.0).unwrap(),
+            Coordinate::new(8.0, 5.0).unwrap(),
+        ];
+        assert_eq!(
+            BoundingBox::enclosing(&triangle).unwrap(),
+            BoundingBox::new(-3.0, 2.0, 8.0, 10.0).unwrap()
+        );
+        assert!(BoundingBox::enclosing(&triangle[..2]).is_err());
+    }
+
+    #[test]
+    fn test_filter_ground() {
+        let aircraft = vec![
+            Aircraft::builder("000001").on_ground(true).build(),
+            Aircraft::builder("000002").build(),
+            Aircraft::builder("000003").on_ground(true).build(),
+        ];
+        let kept = |keep| -> Vec<String> {
+            filter_ground(aircraft.clone(), keep)
+                .into_iter()
+                .map(|ac| ac.icao24)
+                .collect()
+        };
+
+        assert_eq!(kept(GroundFilter::OnlyAirborne), vec!["000002"]);
+        assert_eq!(kept(GroundFilter::OnlyGround), vec!["000001", "000003"]);
+        assert_eq!(kept(GroundFilter::Both), vec!["000001", "000002", "000003"]);
+    }
+
+    #[test]
+    fn test_vertical_trend_symbol() {
+        let with_rate = |rate: f64| Aircraft::builder("000001").vertical_rate(rate).build();
+        assert_eq!(with_rate(8.5).vertical_trend_symbol(), "↑");
+        assert_eq!(with_rate(-6.0).vertical_trend_symbol(), "↓");
+        assert_eq!(with_rate(0.3).vertical_trend_symbol(), "→");
+        assert_eq!(with_rate(-0.5).vertical_trend_symbol(), "→");
+        assert_eq!(
+            Aircraft::builder("000002").build().vertical_trend_symbol(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_filter_flight_levels() {
+        assert!((flight_level_to_m(350) - 10_668.0).abs() < 1e-9);
+
+        let at = |icao24: &str, alt: Option<f64>| {
+            let mut ac = aircraft_at(icao24, None);
+            ac.baro_altitude = alt;
+            ac
+        };
+        let aircraft = vec![
+            at("000001", Some(2_000.0)),
+            at("000002", Some(5_000.0)),
+            at("000003", Some(11_000.0)),
+            at("000004", None),
+        ];
+
+        let kept = filter_flight_levels(aircraft.clone(), Some(100), Some(350));
+        let ids: Vec<&str> = kept.iter().map(|ac| ac.icao24.as_str()).collect();
+        assert_eq!(ids, ["000002"]);
+        assert_eq!(filter_flight_levels(aircraft.clone(), None, None).len(), 4);
+        assert_eq!(filter_altitude(aircraft, None, Some(2_000.0)).len(), 1);
+    }
+
+    #[test]
+    fn test_only_emergencies() {
+        let mut distress = aircraft_at("000001", None);
+        distress.squawk = Some("7700".to_string());
+        let mut routine = aircraft_at("000002", None);
+        routine.squawk = Some("1200".to_string());
+        let aircraft = vec![routine, distress, aircraft_at("000003", None)];
+
+        let found = only_emergencies(aircraft);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].icao24, "000001");
+    }
+
+    #[test]
+    fn test_squawk_meaning() {
+        let squawking = |code: &str| Aircraft::builder("000001").squawk(code).build();
+        assert_eq!(
+            squawking("1200").squawk_meaning(),
+            Some("VFR (North America)")
+        );
+        assert_eq!(squawking("7000").squawk_meaning(), Some("VFR (Europe)"));
+        assert_eq!(squawking("2000").squawk_meaning(), Some("No code assigned"));
+        assert_eq!(
+            squawking("7700").squawk_meaning(),
+            Some("General emergency")
+        );
+        assert_eq!(squawking("4521").squawk_meaning(), None);
+        assert_eq!(Aircraft::builder("000002").build().squawk_meaning(), None);
+    }
+
+    #[test]
+    fn test_to_row_matches_header() {
+        let ac = Aircraft::builder("abc123")
+            .callsign("UAL123")
+            .position(37.75, -122.45)
+            .velocity(250.5)
+            .build();
+        let row = ac.to_row();
+
+        assert_eq!(row.len(), Aircraft::header().len());
+        let column =
+            |name| row[Aircraft::header().iter().position(|&h| h == name).unwrap()].as_str();
+        assert_eq!(column("callsign"), "UAL123");
+        assert_eq!(column("latitude"), "37.75");
+        assert_eq!(column("velocity"), "250.5");
+        assert_eq!(column("on_ground"), "false");
+        assert_eq!(column("squawk"), "");
+    }
+
+    #[test]
+    fn test_callsign_parts() {
+        let parts = |callsign: &str| {
+            Aircraft::builder("000001")
+                .callsign(callsign)
+                .build()
+                .callsign_parts()
+        };
+        let pair = |a: &str, b: &str| Some((a.to_string(), b.to_string()));
+
+        assert_eq!(parts("BAW123 "), pair("BAW", "123"));
+        assert_eq!(parts("DLH4AB"), pair("DLH", "4AB"));
+        assert_eq!(parts("N12345"), pair("N12345", ""));
+        assert_eq!(parts("GBNLA"), pair("GBNLA", ""));
+        assert_eq!(parts("7700"), pair("7700", ""));
+        assert_eq!(parts("  "), None);
+        assert_eq!(Aircraft::builder("000001").build().callsign_parts(), None);
+    }
+
+    #[test]
+    fn test_is_likely_military() {
+        let military = |icao24| aircraft_at(icao24, None).is_likely_military();
+
+        assert!(military("ae1234"));
+        assert!(military("43C0A1"));
+        assert!(!military("a0b1c2"));
+        assert!(!military("4008f6"));
+        assert!(!military("not-hex"));
+    }
+
+    #[test]
+    fn test_airline() {
+        let mut ac = aircraft_at("000001", None);
+        for (callsign, airline) in [
+            ("BAW123", "British Airways"),
+            ("DLH4AB", "Lufthansa"),
+            ("AAL100", "American Airlines"),
+        ] {
+            ac.callsign = Some(callsign.to_string());
+            assert_eq!(ac.airline(), Some(airline));
+        }
+
+        // Defunct operators are not listed.
+        for callsign in ["BER123", "AWE1", "GWI22"] {
+            ac.callsign = Some(callsign.to_string());
+            assert_eq!(ac.airline(), None, "{callsign}");
+        }
+
+        ac.callsign = Some("N12345".to_string());
+        assert_eq!(ac.airline(), None);
+        ac.callsign = Some("BA".to_string());
+        assert_eq!(ac.airline(), None);
+        ac.callsign = None;
+        assert_eq!(ac.airline(), None);
+    }
+
+    #[test]
+    fn test_airline_table_sorted() {
+        assert!(AIRLINE_DESIGNATORS.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn test_field_by_name() {
+        let ac = Aircraft::builder("abc123")
+            .callsign("UAL123")
+            .baro_altitude(10000.0)
+            .build();
+        assert_eq!(ac.field("callsign").unwrap(), "UAL123");
+        assert_eq!(ac.field("baro_altitude").unwrap(), 10000.0);
+        assert!(ac.field("squawk").unwrap().is_null());
+        assert!(matches!(
+            ac.field("altitude"),
+            Err(IfoError::UnknownField(_))
+        ));
+
+        // Every column name is a field, with the value serde would write.
+        let serialized = serde_json::to_value(&ac).unwrap();
+        for name in Aircraft::header() {
+            assert_eq!(ac.field(name).unwrap(), serialized[name], "{name}");
+        }
+    }
+
+    #[test]
+    fn test_normalize_icao24() {
+        assert_eq!(normalize_icao24("3c6444").unwrap(), "3c6444");
+        assert_eq!(normalize_icao24(" 4008F6\n").unwrap(), "4008f6");
+        assert!(matches!(
+            normalize_icao24("3c644"),
+            Err(IfoError::InvalidIcao24(_))
+        ));
+        assert!(matches!(
+            normalize_icao24("3c644g"),
+            Err(IfoError::InvalidIcao24(_))
+        ));
+        // Signs would slip through a plain hex parse.
+        assert!(normalize_icao24("+3c644").is_err());
+    }
+
+    #[test]
+    fn test_icao24_country() {
+        assert_eq!(icao24_country("a0b1c2"), Some("United States"));
+        assert_eq!(icao24_country(" 4008f6 "), Some("United Kingdom"));
+        assert_eq!(icao24_country("4008F6"), Some("United Kingdom"));
+        assert_eq!(icao24_country("3c6444"), Some("Germany"));
+    }
+
+    #[test]
+    fn test_icao24_country_unknown() {
+        assert_eq!(icao24_country("000001"), None);
+        assert_eq!(icao24_country("ffffff"), None);
+        assert_eq!(icao24_country("xyz123"), None);
+        assert_eq!(icao24_country("abc"), None);
+    }
 }
@@ -0,0 +1,680 @@
+//! Formatters that write a [`QueryResult`] as text, JSON, CSV and so on.
+//!
+//! Every format implements [`OutputFormat`]; the CLI picks one from
+//! `--format`, and library users can implement the trait for their own.
+
+use std::io::{self, Write};
+
+use crate::error::{IfoError, Result};
+use crate::models::{self, Aircraft, Coordinate, DistanceUnit};
+use crate::query::QueryResult;
+
+/// Writes a query result in one output format.
+pub trait OutputFormat {
+    /// Write `result` to `w`.
+    fn write(&self, w: &mut dyn Write, result: &QueryResult) -> Result<()>;
+}
+
+/// Human-readable listing with one block per aircraft.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextFormat {
+    /// Print one section per origin country, sorted alphabetically.
+    pub group_by_country: bool,
+    /// Unit for distances from the search center.
+    pub distance_unit: DistanceUnit,
+    /// Decimal places for distances.
+    pub distance_precision: usize,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        Self {
+            group_by_country: false,
+            distance_unit: DistanceUnit::Kilometers,
+            distance_precision: 1,
+        }
+    }
+}
+
+impl TextFormat {
+    fn format_distance(&self, km: f64) -> String {
+        format!(
+            "{:.*} {}",
+            self.distance_precision,
+            self.distance_unit.from_km(km),
+            self.distance_unit.symbol()
+        )
+    }
+
+    fn write_aircraft(
+        &self,
+        w: &mut dyn Write,
+        ac: &Aircraft,
+        center: Option<Coordinate>,
+    ) -> io::Result<()> {
+        writeln!(w, "Callsign: {}", ac.callsign.as_deref().unwrap_or("N/A"))?;
+        writeln!(w, "  ICAO24: {}", ac.icao24)?;
+        if let Some(airline) = ac.airline() {
+            writeln!(w, "  Airline: {}", airline)?;
+        }
+        writeln!(w, "  Country: {}", ac.origin_country)?;
+
+        if let (Some(lat), Some(lon)) = (ac.latitude, ac.longitude) {
+            writeln!(w, "  Position: {:.4}, {:.4}", lat, lon)?;
+        }
+
+        if let (Some(center), Some(position)) = (center, ac.position()) {
+            writeln!(
+                w,
+                "  Distance: {}",
+                self.format_distance(center.distance_to(&position))
+            )?;
+        }
+
+        if let Some(alt) = ac.baro_altitude {
+            match ac.vertical_trend_symbol() {
+                "" => writeln!(w, "  Altitude: {:.0} m", alt)?,
+                trend => writeln!(w, "  Altitude: {:.0} m {}", alt, trend)?,
+            }
+        }
+
+        if let Some(vel) = ac.velocity {
+            writeln!(w, "  Velocity: {:.1} m/s", vel)?;
+        }
+
+        if ac.on_ground {
+            writeln!(w, "  Status: On ground")?;
+        }
+
+        writeln!(w)
+    }
+}
+
+impl OutputFormat for TextFormat {
+    fn write(&self, w: &mut dyn Write, result: &QueryResult) -> Result<()> {
+        if let (Some(_), Some(center)) = (&result.location, result.center) {
+            writeln!(
+                w,
+                "Found location: {} ({:.4}, {:.4})",
+                result.location_name, center.latitude, center.longitude
+            )?;
+        }
+
+        // Queries have a center; offline captures do not.
+        let preposition = if result.center.is_some() {
+            "near"
+        } else {
+            "in"
+        };
+
+        if result.aircraft.is_empty() {
+            writeln!(
+                w,
+                "No aircraft found {} {}",
+                preposition, result.location_name
+            )?;
+            return Ok(());
+        }
+
+        writeln!(
+            w,
+            "Found {} aircraft {} {}:\n",
+            result.aircraft.len(),
+            preposition,
+            result.location_name
+        )?;
+
+        if self.group_by_country {
+            for (country, aircraft) in models::group_by_country(result.aircraft.clone()) {
+                writeln!(w, "== {} ({}) ==\n", country, aircraft.len())?;
+                for ac in &aircraft {
+                    self.write_aircraft(w, ac, result.center)?;
+                }
+            }
+        } else {
+            for ac in &result.aircraft {
+                self.write_aircraft(w, ac, result.center)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A value [`Template`] can substitute, formatted for display.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TemplateField {
+    Icao24,
+    Callsign,
+    Country,
+    Airline,
+    Squawk,
+    Latitude,
+    Longitude,
+    Altitude,
+    AltitudeFt,
+    Velocity,
+    SpeedKt,
+    Heading,
+    VerticalRate,
+    Trend,
+    DistanceKm,
+    OnGround,
+}
+
+impl TemplateField {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "icao24" => Self::Icao24,
+            "callsign" => Self::Callsign,
+            "country" => Self::Country,
+            "airline" => Self::Airline,
+            "squawk" => Self::Squawk,
+            "latitude" => Self::Latitude,
+            "longitude" => Self::Longitude,
+            "altitude" => Self::Altitude,
+            "altitude_ft" => Self::AltitudeFt,
+            "velocity" => Self::Velocity,
+            "speed_kt" => Self::SpeedKt,
+            "heading" => Self::Heading,
+            "vertical_rate" => Self::VerticalRate,
+            "trend" => Self::Trend,
+            "distance_km" => Self::DistanceKm,
+            "on_ground" => Self::OnGround,
+            _ => return None,
+        })
+    }
+
+    /// The formatted value, or `None` if the aircraft does not report it.
+    fn format(self, ac: &Aircraft, center: Option<Coordinate>) -> Option<String> {
+        match self {
+            Self::Icao24 => Some(ac.icao24.clone()),
+            Self::Callsign => ac.callsign.clone(),
+            Self::Country => Some(ac.origin_country.clone()),
+            Self::Airline => ac.airline().map(str::to_string),
+            Self::Squawk => ac.squawk.clone(),
+            Self::Latitude => ac.latitude.map(|lat| format!("{:.4}", lat)),
+            Self::Longitude => ac.longitude.map(|lon| format!("{:.4}", lon)),
+            Self::Altitude => ac.baro_altitude.map(|alt| format!("{:.0}", alt)),
+            Self::AltitudeFt => ac
+                .baro_altitude
+                .map(|alt| format!("{:.0}", alt / models::METRES_PER_FOOT)),
+            Self::Velocity => ac.velocity.map(|vel| format!("{:.1}", vel)),
+            Self::SpeedKt => ac.ground_speed_knots().map(|kt| format!("{:.0}", kt)),
+            Self::Heading => ac.true_track.map(|track| format!("{:.0}", track)),
+            Self::VerticalRate => ac.vertical_rate.map(|rate| format!("{:.1}", rate)),
+            Self::Trend => Some(ac.vertical_trend_symbol().to_string()),
+            Self::DistanceKm => {
+                let km = center?.distance_to(&ac.position()?);
+                Some(format!("{:.1}", km))
+            }
+            Self::OnGround => Some(ac.on_ground.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart {
+    Literal(String),
+    Field(TemplateField),
+}
+
+/// One line per aircraft from a template with `{field}` placeholders.
+///
+/// Placeholders are icao24, callsign, country, airline, squawk, latitude,
+/// longitude, altitude (m), altitude_ft, velocity (m/s), speed_kt, heading
+/// (degrees), vertical_rate (m/s), trend, distance_km and on_ground. Values
+/// an aircraft does not report print as "N/A".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+impl Template {
+    /// Parse a template, failing with [`IfoError::UnknownField`] on an
+    /// unknown placeholder. `{{` and `}}` are literal braces, and so is a
+    /// `{` that is never closed.
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+        while let Some(i) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..i]);
+            let brace = rest.as_bytes()[i];
+            rest = &rest[i + 1..];
+            if rest.as_bytes().first() == Some(&brace) {
+                literal.push(brace as char);
+                rest = &rest[1..];
+                continue;
+            }
+            match (brace, rest.find('}')) {
+                (b'{', Some(end)) => {
+                    let name = rest[..end].trim();
+                    let field = TemplateField::from_name(name)
+                        .ok_or_else(|| IfoError::UnknownField(name.to_string()))?;
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(TemplatePart::Field(field));
+                    rest = &rest[end + 1..];
+                }
+                _ => literal.push(brace as char),
+            }
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Fill in the placeholders for one aircraft; distances are measured
+    /// from `center`.
+    pub fn render(&self, ac: &Aircraft, center: Option<Coordinate>) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Literal(text) => text.clone(),
+                TemplatePart::Field(field) => field
+                    .format(ac, center)
+                    .unwrap_or_else(|| "N/A".to_string()),
+            })
+            .collect()
+    }
+}
+
+impl OutputFormat for Template {
+    fn write(&self, w: &mut dyn Write, result: &QueryResult) -> Result<()> {
+        for ac in &result.aircraft {
+            writeln!(w, "{}", self.render(ac, result.center))?;
+        }
+        Ok(())
+    }
+}
+
+/// Pretty-printed JSON array of aircraft.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonFormat {
+    /// Only include these [`Aircraft::field`] names, in this order; all
+    /// fields when empty.
+    pub fields: Vec<String>,
+}
+
+impl OutputFormat for JsonFormat {
+    fn write(&self, mut w: &mut dyn Write, result: &QueryResult) -> Result<()> {
+        if self.fields.is_empty() {
+            serde_json::to_writer_pretty(&mut w, &result.aircraft)?;
+        } else {
+            let selected = select_fields(&result.aircraft, &self.fields)?;
+            serde_json::to_writer_pretty(&mut w, &selected)?;
+        }
+        writeln!(w)?;
+        Ok(())
+    }
+}
+
+/// One compact JSON object per aircraft per line (NDJSON), the input
+/// format of [`models::read_ndjson`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NdjsonFormat {
+    /// Only include these [`Aircraft::field`] names, in this order; all
+    /// fields when empty.
+    pub fields: Vec<String>,
+}
+
+impl OutputFormat for NdjsonFormat {
+    fn write(&self, w: &mut dyn Write, result: &QueryResult) -> Result<()> {
+        if self.fields.is_empty() {
+            for ac in &result.aircraft {
+                writeln!(w, "{}", serde_json::to_string(ac)?)?;
+            }
+        } else {
+            for object in select_fields(&result.aircraft, &self.fields)? {
+                writeln!(w, "{}", object)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Aircraft as JSON objects holding only `fields`.
+fn select_fields(aircraft: &[Aircraft], fields: &[String]) -> Result<Vec<serde_json::Value>> {
+    aircraft
+        .iter()
+        .map(|ac| {
+            let object = fields
+                .iter()
+                .map(|name| Ok((name.clone(), ac.field(name)?)))
+                .collect::<Result<serde_json::Map<_, _>>>()?;
+            Ok(serde_json::Value::Object(object))
+        })
+        .collect()
+}
+
+/// Comma-separated values with a header row of [`Aircraft::header`] names.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CsvFormat {
+    /// Only include these columns, in this order; all columns when empty.
+    pub fields: Vec<String>,
+}
+
+impl OutputFormat for CsvFormat {
+    fn write(&self, w: &mut dyn Write, result: &QueryResult) -> Result<()> {
+        let header = Aircraft::header();
+        let columns: Vec<usize> = if self.fields.is_empty() {
+            (0..header.len()).collect()
+        } else {
+            self.fields
+                .iter()
+                .map(|name| {
+                    header
+                        .iter()
+                        .position(|column| column == name)
+                        .ok_or_else(|| IfoError::UnknownField(name.clone()))
+                })
+                .collect::<Result<_>>()?
+        };
+
+        writeln!(w, "{}", csv_record(columns.iter().map(|&i| header[i])))?;
+        for ac in &result.aircraft {
+            let row = ac.to_row();
+            writeln!(
+                w,
+                "{}",
+                csv_record(columns.iter().map(|&i| row[i].as_str()))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Join cells into one CSV record, quoting those that contain a comma,
+/// quote or line break.
+fn csv_record<'a>(cells: impl Iterator<Item = &'a str>) -> String {
+    cells
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// MessagePack array of aircraft, using field names as map keys.
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MsgpackFormat;
+
+#[cfg(feature = "msgpack")]
+impl OutputFormat for MsgpackFormat {
+    fn write(&self, w: &mut dyn Write, result: &QueryResult) -> Result<()> {
+        let bytes = rmp_serde::to_vec_named(&result.aircraft)?;
+        w.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+/// Standalone HTML page showing the aircraft on a Leaflet map.
+///
+/// The aircraft are embedded as GeoJSON; Leaflet and the map tiles are
+/// loaded from their CDNs when the page is opened. The map is centered on
+/// the search center if there is one, otherwise fitted to the aircraft.
+#[cfg(feature = "html")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HtmlFormat;
+
+#[cfg(feature = "html")]
+impl OutputFormat for HtmlFormat {
+    fn write(&self, w: &mut dyn Write, result: &QueryResult) -> Result<()> {
+        let page = render_html(&result.location_name, result.center, &result.aircraft);
+        w.write_all(page.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "html")]
+fn render_html(title: &str, center: Option<Coordinate>, aircraft: &[Aircraft]) -> String {
+    // A literal "</" would close the inline script early.
+    let geojson = models::to_geojson(aircraft)
+        .to_string()
+        .replace("</", "<\\/");
+    let center = match center {
+        Some(c) => format!("[{}, {}]", c.latitude, c.longitude),
+        None => "null".to_string(),
+    };
+    let title = escape_html(title);
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Aircraft near {title}</title>
+<link rel="stylesheet" href="https://unpkg.com/leaflet@1.9.4/dist/leaflet.css"
+      integrity="sha256-p4NxAoJBhIIN+hmNHrzRCf9tD/miZyoHS5obTRR9BMY=" crossorigin="">
+<script src="https://unpkg.com/leaflet@1.9.4/dist/leaflet.js"
+        integrity="sha256-20nQCchB9co0qIjJZRGuk2/Z9VM+kNiyxNV1lvTlZBo=" crossorigin=""></script>
+<style>html, body, #map {{ height: 100%; margin: 0; }}</style>
+</head>
+<body>
+<div id="map"></div>
+<script>
+const aircraft = {geojson};
+const center = {center};
+const map = L.map("map");
+L.tileLayer("https://tile.openstreetmap.org/{{z}}/{{x}}/{{y}}.png", {{
+  maxZoom: 19,
+  attribution: "&copy; OpenStreetMap contributors",
+}}).addTo(map);
+const layer = L.geoJSON(aircraft, {{
+  onEachFeature: (feature, marker) => {{
+    const p = feature.properties;
+    const popup = document.createElement("div");
+    popup.textContent = `${{p.callsign || "N/A"}} (${{p.icao24}})`;
+    marker.bindPopup(popup);
+  }},
+}}).addTo(map);
+if (center) {{
+  map.setView(center, 9);
+}} else if (layer.getLayers().length) {{
+  map.fitBounds(layer.getBounds());
+}} else {{
+  map.setView([0, 0], 2);
+}}
+</script>
+</body>
+</html>
+"#
+    )
+}
+
+#[cfg(feature = "html")]
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn result_with(aircraft: Vec<Aircraft>) -> QueryResult {
+        QueryResult {
+            center: Some(Coordinate::new(0.0, 0.0).unwrap()),
+            location_name: "0,0".to_string(),
+            aircraft,
+            snapshot_time: None,
+            elapsed: Duration::ZERO,
+            location: None,
+        }
+    }
+
+    fn render(format: &dyn OutputFormat, result: &QueryResult) -> String {
+        let mut out = Vec::new();
+        format.write(&mut out, result).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_text_format() {
+        let result = result_with(vec![Aircraft::builder("abc123")
+            .callsign("UAL123")
+            .origin_country("United States")
+            .position(0.0, 0.1)
+            .baro_altitude(10000.0)
+            .vertical_rate(0.0)
+            .build()]);
+
+        let text = render(&TextFormat::default(), &result);
+        assert!(text.starts_with("Found 1 aircraft near 0,0:\n\nCallsign: UAL123\n"));
+        assert!(text.contains("  Distance: 11.1 km\n"));
+        assert!(text.contains("  Altitude: 10000 m →\n"));
+
+        let nm = TextFormat {
+            distance_unit: DistanceUnit::NauticalMiles,
+            distance_precision: 2,
+            ..TextFormat::default()
+        };
+        assert!(render(&nm, &result).contains("  Distance: 6.00 nm\n"));
+
+        let grouped = TextFormat {
+            group_by_country: true,
+            ..TextFormat::default()
+        };
+        assert!(render(&grouped, &result).contains("== United States (1) ==\n"));
+
+        let mut capture = result_with(Vec::new());
+        capture.center = None;
+        capture.location_name = "capture.ndjson".to_string();
+        assert_eq!(
+            render(&TextFormat::default(), &capture),
+            "No aircraft found in capture.ndjson\n"
+        );
+    }
+
+    #[test]
+    fn test_template_format() {
+        let template = Template::parse("{callsign} {altitude_ft}ft {distance_km} km").unwrap();
+        let result = result_with(vec![
+            Aircraft::builder("abc123")
+                .callsign("UAL123")
+                .position(0.0, 1.0)
+                .baro_altitude(10000.0)
+                .build(),
+            Aircraft::builder("def456").build(),
+        ]);
+        assert_eq!(
+            render(&template, &result),
+            "UAL123 32808ft 111.2 km\nN/A N/Aft N/A km\n"
+        );
+    }
+
+    #[test]
+    fn test_json_format() {
+        let result = result_with(vec![Aircraft::builder("abc123")
+            .callsign("UAL123")
+            .baro_altitude(10000.0)
+            .build()]);
+
+        let all: serde_json::Value =
+            serde_json::from_str(&render(&JsonFormat::default(), &result)).unwrap();
+        assert_eq!(all[0]["icao24"], "abc123");
+
+        let selected = JsonFormat {
+            fields: vec!["callsign".to_string(), "baro_altitude".to_string()],
+        };
+        let json: serde_json::Value = serde_json::from_str(&render(&selected, &result)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{"callsign": "UAL123", "baro_altitude": 10000.0}])
+        );
+    }
+
+    #[test]
+    fn test_ndjson_format_round_trips() {
+        let result = result_with(vec![
+            Aircraft::builder("abc123").callsign("UAL123").build(),
+            Aircraft::builder("def456").baro_altitude(10000.0).build(),
+        ]);
+
+        let ndjson = render(&NdjsonFormat::default(), &result);
+        assert_eq!(ndjson.lines().count(), 2);
+        let read = crate::models::read_ndjson(ndjson.as_bytes()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&read).unwrap(),
+            serde_json::to_value(&result.aircraft).unwrap()
+        );
+
+        let selected = NdjsonFormat {
+            fields: vec!["icao24".to_string()],
+        };
+        assert_eq!(
+            render(&selected, &result),
+            "{\"icao24\":\"abc123\"}\n{\"icao24\":\"def456\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_format() {
+        let result = result_with(vec![Aircraft::builder("abc123")
+            .callsign("UAL,123")
+            .baro_altitude(10000.0)
+            .build()]);
+        let csv = CsvFormat {
+            fields: vec!["callsign".to_string(), "baro_altitude".to_string()],
+        };
+        assert_eq!(
+            render(&csv, &result),
+            "callsign,baro_altitude\n\"UAL,123\",10000\n"
+        );
+
+        let full = render(&CsvFormat::default(), &result);
+        assert_eq!(full.lines().next().unwrap(), Aircraft::header().join(","));
+    }
+
+    #[cfg(feature = "html")]
+    #[test]
+    fn test_html_format_embeds_geojson() {
+        let mut result = result_with(vec![Aircraft::builder("abc123")
+            .callsign("</script>")
+            .position(37.75, -122.45)
+            .build()]);
+        result.center = Some(Coordinate::new(37.7, -122.4).unwrap());
+        result.location_name = "San Francisco & Bay".to_string();
+
+        let page = render(&HtmlFormat, &result);
+
+        assert!(page.contains(r#"<div id="map"></div>"#));
+        assert!(page.contains(r#"const aircraft = {"features":[{"geometry""#));
+        assert!(page.contains(r#""icao24":"abc123""#));
+        assert!(page.contains(r#""callsign":"<\/script>""#));
+        assert!(page.contains("const center = [37.7, -122.4];"));
+        assert!(page.contains("<title>Aircraft near San Francisco &amp; Bay</title>"));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack_format_round_trip() {
+        let state: Vec<serde_json::Value> = serde_json::from_str(
+            r#"["abc123", "UAL123  ", "United States", 1700000000, 1700000005,
+                -122.45, 37.75, 10000.0, false, 250.5, 90.0, -1.5, null, 10100.0, "1200", false, 0]"#,
+        )
+        .unwrap();
+        let result = result_with(vec![Aircraft::from_state_vector(state).unwrap()]);
+
+        let mut bytes = Vec::new();
+        MsgpackFormat.write(&mut bytes, &result).unwrap();
+        let decoded: Vec<Aircraft> = rmp_serde::from_slice(&bytes).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&result.aircraft).unwrap()
+        );
+    }
+}
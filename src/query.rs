@@ -0,0 +1,712 @@
+//! End-to-end query pipeline: resolve a location, build the search area and
+//! fetch the aircraft inside it.
+//!
+//! This is the code path behind the CLI, kept free of any presentation so
+//! every output format (and library users) can share it.
+
+use std::future::Future;
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use reqwest::Url;
+use web_time::Instant;
+
+use crate::api::OpenSkyClient;
+use crate::error::{IfoError, Result};
+use crate::geocoding::{Geocoder, GeocoderBackend};
+use crate::models::{
+    self, Aircraft, BoundingBox, Coordinate, GroundFilter, Location, SearchRadius,
+};
+use crate::source::AircraftSource;
+
+/// Where to search for aircraft.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum QueryInput {
+    /// A known coordinate.
+    Coords(Coordinate),
+    /// A place name to geocode first.
+    Place(String),
+    /// An exact search area; the radius is not used.
+    Area(BoundingBox),
+}
+
+impl QueryInput {
+    /// Treat `input` as coordinates if it parses as `lat,lon`, otherwise as
+    /// a place name to geocode.
+    pub fn detect(input: &str) -> Self {
+        match Coordinate::parse(input) {
+            Ok(coord) => QueryInput::Coords(coord),
+            Err(_) => QueryInput::Place(input.trim().to_string()),
+        }
+    }
+}
+
+/// Outcome of a query, independent of how it will be displayed.
+#[derive(Debug, Clone)]
+pub struct QueryResult {
+    /// Center of the search area.
+    pub center: Option<Coordinate>,
+    /// Human-readable name of the searched location.
+    pub location_name: String,
+    pub aircraft: Vec<Aircraft>,
+    /// Unix timestamp of the OpenSky snapshot.
+    pub snapshot_time: Option<i64>,
+    /// Total time taken to produce the result, geocoding included.
+    pub elapsed: Duration,
+    /// Geocoding result for place queries, with its OpenStreetMap class.
+    pub location: Option<Location>,
+}
+
+/// Run a query against the public OpenSky and Nominatim services.
+pub async fn run_query(
+    input: &QueryInput,
+    radius: SearchRadius,
+    timeout: u64,
+) -> Result<QueryResult> {
+    let client = OpenSkyClient::new(timeout)?;
+    let geocoder = Geocoder::new(timeout)?;
+    run_query_with(&client, &geocoder, input, radius).await
+}
+
+/// Run a query using the given clients.
+///
+/// Any [`AircraftSource`] can stand in for the OpenSky client, and any
+/// [`GeocoderBackend`] for Nominatim.
+pub async fn run_query_with(
+    client: &impl AircraftSource,
+    geocoder: &impl GeocoderBackend,
+    input: &QueryInput,
+    radius: SearchRadius,
+) -> Result<QueryResult> {
+    let started = Instant::now();
+    let mut location = None;
+    let (center, location_name) = match input {
+        QueryInput::Coords(coord) => (*coord, format!("{},{}", coord.latitude, coord.longitude)),
+        QueryInput::Place(place) => match geocoder.geocode(place).await? {
+            Some(found) => {
+                let coord = Coordinate::new(found.lat, found.lon)?;
+                let name = found.display_name.clone();
+                location = Some(found);
+                (coord, name)
+            }
+            None => return Err(IfoError::LocationNotFound(place.clone())),
+        },
+        QueryInput::Area(bbox) => (
+            bbox.center(),
+            format!(
+                "{},{},{},{}",
+                bbox.lat_min, bbox.lon_min, bbox.lat_max, bbox.lon_max
+            ),
+        ),
+    };
+
+    let bbox = match input {
+        QueryInput::Area(bbox) => *bbox,
+        _ => radius.bbox_around(center),
+    };
+    let snapshot = client.snapshot_in_area(bbox).await?;
+
+    Ok(QueryResult {
+        center: Some(center),
+        location_name,
+        aircraft: snapshot.aircraft,
+        snapshot_time: snapshot.time,
+        elapsed: started.elapsed(),
+        location,
+    })
+}
+
+/// Run one query per entry of `places` against the public services; see
+/// [`run_batch_with`].
+pub async fn run_batch(
+    places: &[&str],
+    radius: SearchRadius,
+    timeout: u64,
+) -> Result<Vec<Result<QueryResult>>> {
+    let client = OpenSkyClient::new(timeout)?;
+    let geocoder = Geocoder::new(timeout)?;
+    Ok(run_batch_with(&client, &geocoder, places, radius).await)
+}
+
+/// Run one query per entry of `places`, each a place name or `"lat,lon"`
+/// string, returning their results in input order.
+///
+/// All queries run concurrently. Geocoding still goes out one request per
+/// second through the geocoder's rate limiter, and each aircraft fetch
+/// starts as soon as its own location is known. A failed entry, such as an
+/// unknown place, leaves an error in its slot without affecting the rest.
+pub async fn run_batch_with(
+    client: &impl AircraftSource,
+    geocoder: &impl GeocoderBackend,
+    places: &[&str],
+    radius: SearchRadius,
+) -> Vec<Result<QueryResult>> {
+    let inputs: Vec<QueryInput> = places
+        .iter()
+        .map(|place| QueryInput::detect(place))
+        .collect();
+    join_all(
+        inputs
+            .iter()
+            .map(|input| run_query_with(client, geocoder, input, radius)),
+    )
+    .await
+}
+
+/// Declarative form of the whole pipeline: location, search area and the
+/// filters applied to what comes back.
+///
+/// ```no_run
+/// # async fn example() -> ifo::Result<()> {
+/// use ifo::api::OpenSkyClient;
+/// use ifo::geocoding::Geocoder;
+/// use ifo::query::Query;
+///
+/// let client = OpenSkyClient::new(10)?;
+/// let geocoder = Geocoder::new(10)?;
+/// let result = Query::new()
+///     .place("London")
+///     .radius_km(50.0)
+///     .airborne()
+///     .sort_by_distance()
+///     .limit(20)
+///     .execute(&client, &geocoder)
+///     .await?;
+/// println!("{} aircraft", result.aircraft.len());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Query {
+    input: Option<QueryInput>,
+    radius: SearchRadius,
+    ground: GroundFilter,
+    emergencies_only: bool,
+    sort_by_distance: bool,
+    limit: Option<usize>,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Self {
+            input: None,
+            radius: SearchRadius::Degrees(0.5),
+            ground: GroundFilter::Both,
+            emergencies_only: false,
+            sort_by_distance: false,
+            limit: None,
+        }
+    }
+}
+
+impl Query {
+    /// Start a query with a 0.5° radius and no filters. A location must be
+    /// set before [`execute`](Self::execute).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Search around a place name, geocoded when the query runs.
+    pub fn place(mut self, place: impl Into<String>) -> Self {
+        self.input = Some(QueryInput::Place(place.into()));
+        self
+    }
+
+    /// Search around known coordinates.
+    pub fn coords(mut self, coord: Coordinate) -> Self {
+        self.input = Some(QueryInput::Coords(coord));
+        self
+    }
+
+    /// Search exactly this area instead of a radius around a point.
+    pub fn bbox(mut self, bbox: BoundingBox) -> Self {
+        self.input = Some(QueryInput::Area(bbox));
+        self
+    }
+
+    /// Search around an already classified location.
+    pub fn input(mut self, input: QueryInput) -> Self {
+        self.input = Some(input);
+        self
+    }
+
+    /// Set the size of the search area.
+    pub fn radius(mut self, radius: SearchRadius) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Set the size of the search area in kilometres.
+    pub fn radius_km(self, km: f64) -> Self {
+        self.radius(SearchRadius::Kilometers(km))
+    }
+
+    /// Keep only aircraft that are not on the ground.
+    pub fn airborne(self) -> Self {
+        self.ground(GroundFilter::OnlyAirborne)
+    }
+
+    /// Keep aircraft by their `on_ground` flag; see [`models::filter_ground`].
+    pub fn ground(mut self, keep: GroundFilter) -> Self {
+        self.ground = keep;
+        self
+    }
+
+    /// Keep only aircraft squawking an emergency code.
+    pub fn emergencies_only(mut self) -> Self {
+        self.emergencies_only = true;
+        self
+    }
+
+    /// Order aircraft nearest to the search center first. Aircraft without
+    /// a position go last.
+    pub fn sort_by_distance(mut self) -> Self {
+        self.sort_by_distance = true;
+        self
+    }
+
+    /// Return at most `n` aircraft, after filtering and sorting.
+    pub fn limit(mut self, n: usize) -> Self {
+        self.limit = Some(n);
+        self
+    }
+
+    /// Run the pipeline: geocode if needed, fetch the area, then filter,
+    /// sort and truncate.
+    ///
+    /// Returns [`IfoError::MissingLocation`] if no location was set.
+    pub async fn execute(
+        &self,
+        client: &impl AircraftSource,
+        geocoder: &impl GeocoderBackend,
+    ) -> Result<QueryResult> {
+        let input = self.input.as_ref().ok_or(IfoError::MissingLocation)?;
+        let mut result = run_query_with(client, geocoder, input, self.radius).await?;
+
+        let mut aircraft = models::filter_ground(result.aircraft, self.ground);
+        if self.emergencies_only {
+            aircraft = models::only_emergencies(aircraft);
+        }
+        if let (true, Some(center)) = (self.sort_by_distance, result.center) {
+            // Unpositioned aircraft sort as infinitely far away.
+            aircraft.sort_by(|a, b| {
+                let distance = |ac: &Aircraft| {
+                    ac.position()
+                        .map_or(f64::INFINITY, |pos| center.distance_to(&pos))
+                };
+                distance(a).total_cmp(&distance(b))
+            });
+        }
+        if let Some(n) = self.limit {
+            aircraft.truncate(n);
+        }
+
+        result.aircraft = aircraft;
+        Ok(result)
+    }
+}
+
+/// The first request [`run_query`] would send for `input`, without sending
+/// it.
+///
+/// For coordinates that is the OpenSky request itself. For a place name it
+/// is the geocoding request: the OpenSky area depends on its answer.
+pub fn plan_query(input: &QueryInput, radius: SearchRadius, timeout: u64) -> Result<Url> {
+    let client = OpenSkyClient::new(timeout)?;
+    let geocoder = Geocoder::new(timeout)?;
+    plan_query_with(&client, &geocoder, input, radius)
+}
+
+/// Like [`plan_query`], using the given clients.
+pub fn plan_query_with(
+    client: &OpenSkyClient,
+    geocoder: &impl GeocoderBackend,
+    input: &QueryInput,
+    radius: SearchRadius,
+) -> Result<Url> {
+    match input {
+        QueryInput::Coords(coord) => client.states_url(radius.bbox_around(*coord)),
+        QueryInput::Place(place) => geocoder.search_url(place),
+        QueryInput::Area(bbox) => client.states_url(*bbox),
+    }
+}
+
+/// Bound a whole query, however many requests it makes, by one deadline.
+///
+/// Returns [`IfoError::Timeout`] if `query` has not finished in time.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn with_deadline<T>(
+    deadline: Duration,
+    query: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::time::timeout(deadline, query)
+        .await
+        .map_err(|_| deadline_exceeded(deadline))?
+}
+
+/// Bound a whole query, however many requests it makes, by one deadline.
+///
+/// Returns [`IfoError::Timeout`] if `query` has not finished in time.
+#[cfg(target_arch = "wasm32")]
+pub async fn with_deadline<T>(
+    deadline: Duration,
+    query: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    use futures_util::future::{select, Either};
+
+    let query = std::pin::pin!(query);
+    let timer = std::pin::pin!(futures_timer::Delay::new(deadline));
+    match select(query, timer).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(deadline_exceeded(deadline)),
+    }
+}
+
+/// [`IfoError::Timeout`] for `deadline`, in whole seconds rounded up so a
+/// sub-second deadline does not read as "0 seconds".
+fn deadline_exceeded(deadline: Duration) -> IfoError {
+    IfoError::Timeout {
+        seconds: deadline.as_secs() + u64::from(deadline.subsec_nanos() > 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+
+    const STATES_BODY: &str = r#"{"time": 1700000000, "states": [
+        ["abc123", "UAL123  ", "United States", 1699999990, 1699999995,
+         -122.45, 37.75, 10000.0, false, 250.5, 90.0, 0.0, null, 10100.0, "1200", false, 0]
+    ]}"#;
+
+    fn clients(server: &Server) -> (OpenSkyClient, Geocoder) {
+        let client = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let geocoder = Geocoder::builder().base_url(server.url()).build().unwrap();
+        (client, geocoder)
+    }
+
+    #[test]
+    fn test_detect_input() {
+        assert_eq!(
+            QueryInput::detect("51.5,-0.1"),
+            QueryInput::Coords(Coordinate::new(51.5, -0.1).unwrap())
+        );
+        assert_eq!(
+            QueryInput::detect(" London, UK "),
+            QueryInput::Place("London, UK".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_query_with_coords() {
+        let mut server = Server::new_async().await;
+        let states = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(STATES_BODY)
+            .create_async()
+            .await;
+        let (client, geocoder) = clients(&server);
+
+        let input = QueryInput::Coords(Coordinate::new(37.7, -122.4).unwrap());
+        let result = run_query_with(&client, &geocoder, &input, SearchRadius::Degrees(0.5))
+            .await
+            .unwrap();
+
+        states.assert_async().await;
+        assert_eq!(result.location_name, "37.7,-122.4");
+        assert_eq!(result.snapshot_time, Some(1700000000));
+        assert_eq!(result.aircraft.len(), 1);
+        assert_eq!(result.aircraft[0].callsign.as_deref(), Some("UAL123"));
+        assert!(result.elapsed > Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_run_query_with_place() {
+        let mut server = Server::new_async().await;
+        let search = server
+            .mock("GET", "/search")
+            .match_query(Matcher::UrlEncoded("q".into(), "San Francisco".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[{"lat": "37.7749", "lon": "-122.4194", "display_name": "San Francisco, CA"}]"#,
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(STATES_BODY)
+            .create_async()
+            .await;
+        let (client, geocoder) = clients(&server);
+
+        let input = QueryInput::Place("San Francisco".to_string());
+        let result = run_query_with(&client, &geocoder, &input, SearchRadius::Degrees(0.5))
+            .await
+            .unwrap();
+
+        search.assert_async().await;
+        assert_eq!(result.location_name, "San Francisco, CA");
+        assert_eq!(
+            result.center,
+            Some(Coordinate::new(37.7749, -122.4194).unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_query_with_area() {
+        let server = Server::new_async().await;
+        let (client, geocoder) = clients(&server);
+        let bbox = BoundingBox::new(37.0, -123.0, 38.0, -122.0).unwrap();
+
+        let url = plan_query_with(
+            &client,
+            &geocoder,
+            &QueryInput::Area(bbox),
+            SearchRadius::Degrees(0.5),
+        )
+        .unwrap();
+        assert_eq!(
+            url.as_str(),
+            format!(
+                "{}/states/all?lamin=37&lomin=-123&lamax=38&lomax=-122",
+                server.url()
+            )
+        );
+
+        let source = crate::testing::MockAircraftSource::new(vec![
+            Aircraft::builder("000001").position(37.9, -122.1).build(),
+            Aircraft::builder("000002").position(38.2, -122.5).build(),
+        ])
+        .filter_by_bbox(true);
+        let result = run_query_with(
+            &source,
+            &geocoder,
+            &QueryInput::Area(bbox),
+            SearchRadius::Degrees(0.01),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.location_name, "37,-123,38,-122");
+        assert_eq!(result.center, Some(Coordinate::new(37.5, -122.5).unwrap()));
+        assert_eq!(result.aircraft.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_query_with_mock_source() {
+        let server = Server::new_async().await;
+        let (_, geocoder) = clients(&server);
+        let source = crate::testing::MockAircraftSource::new(vec![
+            Aircraft::builder("000001").position(37.7, -122.4).build(),
+            Aircraft::builder("000002").position(40.7, -74.0).build(),
+        ])
+        .filter_by_bbox(true);
+
+        let input = QueryInput::Coords(Coordinate::new(37.7, -122.4).unwrap());
+        let result = run_query_with(&source, &geocoder, &input, SearchRadius::Degrees(0.5))
+            .await
+            .unwrap();
+
+        assert_eq!(result.aircraft.len(), 1);
+        assert_eq!(result.aircraft[0].icao24, "000001");
+    }
+
+    #[tokio::test]
+    async fn test_run_query_with_unknown_place() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/search")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create_async()
+            .await;
+        let (client, geocoder) = clients(&server);
+
+        let input = QueryInput::Place("Nowhere".to_string());
+        let result = run_query_with(&client, &geocoder, &input, SearchRadius::Degrees(0.5)).await;
+
+        assert!(matches!(result, Err(IfoError::LocationNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_run_batch_keeps_order_and_failures() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/search")
+            .match_query(Matcher::UrlEncoded("q".into(), "San Francisco".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[{"lat": "37.7749", "lon": "-122.4194", "display_name": "San Francisco, CA"}]"#,
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/search")
+            .match_query(Matcher::UrlEncoded("q".into(), "Atlantis".into()))
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create_async()
+            .await;
+        let states = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(STATES_BODY)
+            .expect(2)
+            .create_async()
+            .await;
+        let (client, geocoder) = clients(&server);
+
+        let results = run_batch_with(
+            &client,
+            &geocoder,
+            &["Atlantis", "San Francisco", "51.5,-0.1"],
+            SearchRadius::Degrees(0.5),
+        )
+        .await;
+
+        assert_eq!(results.len(), 3);
+        assert!(
+            matches!(&results[0], Err(IfoError::LocationNotFound(place)) if place == "Atlantis")
+        );
+        assert_eq!(
+            results[1].as_ref().unwrap().location_name,
+            "San Francisco, CA"
+        );
+        assert_eq!(results[2].as_ref().unwrap().location_name, "51.5,-0.1");
+        states.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_query_builder_full_chain() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/search")
+            .match_query(Matcher::UrlEncoded("q".into(), "London".into()))
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"lat": "51.5074", "lon": "-0.1278", "display_name": "London"}]"#)
+            .create_async()
+            .await;
+        let (_, geocoder) = clients(&server);
+        let source = crate::testing::MockAircraftSource::new(vec![
+            Aircraft::builder("000001").position(51.9, -0.1).build(),
+            Aircraft::builder("000002").position(51.5, -0.1).build(),
+            Aircraft::builder("000003")
+                .position(51.5, -0.13)
+                .on_ground(true)
+                .build(),
+            Aircraft::builder("000004").position(51.7, -0.1).build(),
+            Aircraft::builder("000005").build(),
+        ]);
+
+        let result = Query::new()
+            .place("London")
+            .radius_km(50.0)
+            .airborne()
+            .sort_by_distance()
+            .limit(3)
+            .execute(&source, &geocoder)
+            .await
+            .unwrap();
+
+        let icao24s: Vec<_> = result
+            .aircraft
+            .iter()
+            .map(|ac| ac.icao24.as_str())
+            .collect();
+        assert_eq!(icao24s, ["000002", "000004", "000001"]);
+        assert_eq!(result.location_name, "London");
+    }
+
+    #[tokio::test]
+    async fn test_query_without_location() {
+        let server = Server::new_async().await;
+        let (client, geocoder) = clients(&server);
+
+        let result = Query::new().execute(&client, &geocoder).await;
+
+        assert!(matches!(result, Err(IfoError::MissingLocation)));
+    }
+
+    #[tokio::test]
+    async fn test_plan_query_sends_nothing() {
+        let mut server = Server::new_async().await;
+        let states = server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let search = server
+            .mock("GET", "/search")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let (client, geocoder) = clients(&server);
+
+        let input = QueryInput::Coords(Coordinate::new(37.7, -122.4).unwrap());
+        let url = plan_query_with(&client, &geocoder, &input, SearchRadius::Degrees(0.5)).unwrap();
+        assert_eq!(
+            url.as_str(),
+            format!(
+                "{}/states/all?lamin=37.2&lomin=-122.9&lamax=38.2&lomax=-121.9",
+                server.url()
+            )
+        );
+
+        let input = QueryInput::Place("San Francisco".to_string());
+        let url = plan_query_with(&client, &geocoder, &input, SearchRadius::Degrees(0.5)).unwrap();
+        assert_eq!(
+            url.as_str(),
+            format!(
+                "{}/search?q=San+Francisco&format=json&limit=1",
+                server.url()
+            )
+        );
+
+        states.assert_async().await;
+        search.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_with_deadline_fires_on_slow_query() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(500));
+                w.write_all(STATES_BODY.as_bytes())
+            })
+            .create_async()
+            .await;
+        let (client, geocoder) = clients(&server);
+
+        let input = QueryInput::Coords(Coordinate::new(37.7, -122.4).unwrap());
+        let result = with_deadline(
+            Duration::from_millis(100),
+            run_query_with(&client, &geocoder, &input, SearchRadius::Degrees(0.5)),
+        )
+        .await;
+
+        let err = result.unwrap_err();
+        assert!(matches!(err, IfoError::Timeout { seconds: 1 }));
+        assert_eq!(err.to_string(), "Timeout after 1 seconds");
+        assert!(matches!(
+            deadline_exceeded(Duration::from_secs(20)),
+            IfoError::Timeout { seconds: 20 }
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+//! Where aircraft snapshots come from.
+//!
+//! The query pipeline only needs "aircraft in this box", so it is written
+//! against [`AircraftSource`] rather than the OpenSky client directly. That
+//! lets captures, mirrors or test fixtures stand in for the live API.
+
+use std::future::Future;
+use std::pin::Pin;
+
+#[cfg(feature = "net")]
+use futures_util::future::join_all;
+
+use crate::error::Result;
+#[cfg(feature = "net")]
+use crate::models::{self, Aircraft};
+use crate::models::{AreaSnapshot, BoundingBox};
+
+/// `Send` everywhere except wasm32.
+///
+/// Futures built on the browser's fetch API are not `Send`, and there is
+/// only one thread to run them on anyway, so the source and geocoder traits
+/// ask for `MaybeSend` futures instead of `Send` ones.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+/// `Send` everywhere except wasm32.
+///
+/// Futures built on the browser's fetch API are not `Send`, and there is
+/// only one thread to run them on anyway, so the source and geocoder traits
+/// ask for `MaybeSend` futures instead of `Send` ones.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// The boxed future returned by [`DynAircraftSource`]; `Send` except on
+/// wasm32, like [`MaybeSend`].
+#[cfg(not(target_arch = "wasm32"))]
+pub type BoxSnapshotFuture<'a> = Pin<Box<dyn Future<Output = Result<AreaSnapshot>> + Send + 'a>>;
+
+/// The boxed future returned by [`DynAircraftSource`]; `Send` except on
+/// wasm32, like [`MaybeSend`].
+#[cfg(target_arch = "wasm32")]
+pub type BoxSnapshotFuture<'a> = Pin<Box<dyn Future<Output = Result<AreaSnapshot>> + 'a>>;
+
+/// A provider of aircraft positions by area.
+pub trait AircraftSource {
+    /// Aircraft within `bbox`, together with the time the data was valid.
+    fn snapshot_in_area(
+        &self,
+        bbox: BoundingBox,
+    ) -> impl Future<Output = Result<AreaSnapshot>> + MaybeSend;
+}
+
+/// Object-safe form of [`AircraftSource`], for holding different kinds of
+/// source side by side as `&dyn DynAircraftSource`.
+///
+/// Implemented for every [`AircraftSource`]; the future is boxed.
+pub trait DynAircraftSource {
+    /// [`AircraftSource::snapshot_in_area`], boxed.
+    fn boxed_snapshot_in_area(&self, bbox: BoundingBox) -> BoxSnapshotFuture<'_>;
+}
+
+impl<S: AircraftSource> DynAircraftSource for S {
+    fn boxed_snapshot_in_area(&self, bbox: BoundingBox) -> BoxSnapshotFuture<'_> {
+        Box::pin(self.snapshot_in_area(bbox))
+    }
+}
+
+/// Query every source for `bbox` concurrently and merge what comes back.
+///
+/// Aircraft reported by more than one source are de-duplicated by
+/// [`models::dedup_freshest`]: the report with the latest `last_contact`
+/// wins (ties keep the earlier source), and the result is sorted by
+/// `icao24` whatever order the sources answered in.
+///
+/// A failing source is logged and skipped; an error is returned only when
+/// every source failed, and then it is the first source's. No sources means
+/// no aircraft.
+#[cfg(feature = "net")]
+pub async fn merge_sources(
+    sources: &[&dyn DynAircraftSource],
+    bbox: BoundingBox,
+) -> Result<Vec<Aircraft>> {
+    let results = join_all(
+        sources
+            .iter()
+            .map(|source| source.boxed_snapshot_in_area(bbox)),
+    )
+    .await;
+
+    let mut reports: Vec<Aircraft> = Vec::new();
+    let mut first_error = None;
+    let mut any_ok = sources.is_empty();
+    for (i, result) in results.into_iter().enumerate() {
+        match result {
+            Ok(snapshot) => {
+                any_ok = true;
+                reports.extend(snapshot.aircraft);
+            }
+            Err(e) => {
+                tracing::warn!(source = i, error = %e, "source failed; merging the rest");
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    match first_error {
+        Some(e) if !any_ok => Err(e),
+        _ => Ok(models::dedup_freshest(reports)),
+    }
+}
+
+#[cfg(all(test, feature = "net"))]
+mod tests {
+    use super::*;
+    use crate::api::OpenSkyClient;
+    use crate::error::IfoError;
+    use crate::testing::MockAircraftSource;
+    use mockito::{Matcher, Server};
+
+    #[tokio::test]
+    async fn test_merge_sources_keeps_freshest() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/states/all")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"time": 1700000000, "states": [
+                    ["000003", "ONLY3   ", "Germany", null, null, 0.0, 51.0, 900.0, false, null, null, null, null, null, null, false, 0],
+                    ["000001", "NEW1    ", "Germany", 200, 200, 0.0, 51.0, 900.0, false, null, null, null, null, null, null, false, 0]
+                ]}"#,
+            )
+            .create_async()
+            .await;
+        let opensky = OpenSkyClient::builder()
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let mock = MockAircraftSource::new(vec![
+            Aircraft::builder("000001")
+                .callsign("OLD1")
+                .last_contact(100)
+                .build(),
+            Aircraft::builder("000002")
+                .callsign("ONLY2")
+                .last_contact(100)
+                .build(),
+        ]);
+        let down = MockAircraftSource::default().failing();
+        let bbox = BoundingBox::new(50.0, -1.0, 52.0, 1.0).unwrap();
+
+        let sources: [&dyn DynAircraftSource; 3] = [&mock, &down, &opensky];
+        let merged = merge_sources(&sources, bbox).await.unwrap();
+        let callsigns: Vec<Option<&str>> = merged.iter().map(|ac| ac.callsign.as_deref()).collect();
+        assert_eq!(callsigns, [Some("NEW1"), Some("ONLY2"), Some("ONLY3")]);
+
+        // Listing the sources differently gives the same order.
+        let reordered: [&dyn DynAircraftSource; 3] = [&opensky, &mock, &down];
+        let again = merge_sources(&reordered, bbox).await.unwrap();
+        let icao24s = |list: &[Aircraft]| -> Vec<String> {
+            list.iter().map(|ac| ac.icao24.clone()).collect()
+        };
+        assert_eq!(icao24s(&merged), icao24s(&again));
+
+        assert!(matches!(
+            merge_sources(&[&down, &down], bbox).await,
+            Err(IfoError::ApiError { status: 503, .. })
+        ));
+        assert!(merge_sources(&[], bbox).await.unwrap().is_empty());
+    }
+}
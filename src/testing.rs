@@ -0,0 +1,107 @@
+//! Deterministic stand-ins for network services, for tests and examples.
+//!
+//! Enabled with the `test-util` feature.
+
+use std::future::Future;
+
+use crate::error::{IfoError, Result};
+use crate::models::{Aircraft, AreaSnapshot, BoundingBox};
+use crate::source::{AircraftSource, MaybeSend};
+
+/// An [`AircraftSource`] that answers every query from a fixed list.
+#[derive(Debug, Clone, Default)]
+pub struct MockAircraftSource {
+    aircraft: Vec<Aircraft>,
+    time: Option<i64>,
+    filter_by_bbox: bool,
+    failing: bool,
+}
+
+impl MockAircraftSource {
+    /// Return `aircraft` unchanged for any bounding box.
+    pub fn new(aircraft: Vec<Aircraft>) -> Self {
+        Self {
+            aircraft,
+            ..Self::default()
+        }
+    }
+
+    /// Only return aircraft positioned inside the queried box, like the real
+    /// API. Aircraft without a position are then left out.
+    pub fn filter_by_bbox(mut self, filter: bool) -> Self {
+        self.filter_by_bbox = filter;
+        self
+    }
+
+    /// Fail every query with a 503 [`IfoError::ApiError`], like a source
+    /// that is down.
+    pub fn failing(mut self) -> Self {
+        self.failing = true;
+        self
+    }
+
+    /// Snapshot time to report. Defaults to `None`.
+    pub fn time(mut self, time: i64) -> Self {
+        self.time = Some(time);
+        self
+    }
+}
+
+impl AircraftSource for MockAircraftSource {
+    fn snapshot_in_area(
+        &self,
+        bbox: BoundingBox,
+    ) -> impl Future<Output = Result<AreaSnapshot>> + MaybeSend {
+        let failing = self.failing;
+        let aircraft = self
+            .aircraft
+            .iter()
+            .filter(|ac| {
+                !self.filter_by_bbox || ac.position().is_some_and(|pos| bbox.contains(pos))
+            })
+            .cloned()
+            .collect();
+        let snapshot = AreaSnapshot {
+            time: self.time,
+            aircraft,
+        };
+        async move {
+            if failing {
+                return Err(IfoError::ApiError {
+                    status: 503,
+                    message: "Service Unavailable".to_string(),
+                });
+            }
+            Ok(snapshot)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_filters_by_bbox() {
+        let aircraft = vec![
+            Aircraft::builder("000001").position(51.5, -0.1).build(),
+            Aircraft::builder("000002").position(48.9, 2.4).build(),
+            Aircraft::builder("000003").build(),
+        ];
+        let bbox = BoundingBox::new(51.0, -1.0, 52.0, 1.0).unwrap();
+
+        let unfiltered = MockAircraftSource::new(aircraft.clone()).time(1_700_000_000);
+        let snapshot = tokio_test::block_on(unfiltered.snapshot_in_area(bbox)).unwrap();
+        assert_eq!(snapshot.aircraft.len(), 3);
+        assert_eq!(snapshot.time, Some(1_700_000_000));
+
+        let filtered = MockAircraftSource::new(aircraft).filter_by_bbox(true);
+        let snapshot = tokio_test::block_on(filtered.snapshot_in_area(bbox)).unwrap();
+        let icao24s: Vec<&str> = snapshot
+            .aircraft
+            .iter()
+            .map(|ac| ac.icao24.as_str())
+            .collect();
+        assert_eq!(icao24s, vec!["000001"]);
+    }
+}
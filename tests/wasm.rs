@@ -0,0 +1,145 @@
+//! The clients running in a JavaScript host, against a stubbed `fetch`.
+//!
+//! Run with `wasm-bindgen-test-runner` as the wasm32 runner:
+//! `cargo test --target wasm32-unknown-unknown --test wasm` (see README).
+
+#![cfg(target_arch = "wasm32")]
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use governor::{Quota, RateLimiter};
+use ifo::api::{OpenSkyClient, WebClock};
+use ifo::geocoding::Geocoder;
+use ifo::query::run_query_with;
+use ifo::{with_deadline, IfoError, QueryInput, SearchRadius};
+use nonzero::nonzero;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen_test::wasm_bindgen_test;
+use web_time::Instant;
+
+#[wasm_bindgen(inline_js = r#"
+const routes = {};
+
+// Replies are served in order per URL (origin and path); the last one
+// repeats. A `null` reply never resolves.
+export function stub_fetch(table) {
+    Object.assign(routes, JSON.parse(table));
+    globalThis.fetch = async (request) => {
+        const url = new URL(request.url);
+        const queue = routes[url.origin + url.pathname];
+        const reply = queue.length > 1 ? queue.shift() : queue[0];
+        if (reply === null) {
+            return new Promise(() => {});
+        }
+        const response = new Response(reply.body, { status: reply.status, headers: reply.headers });
+        // A real fetch fills in the URL it answered for; reqwest reads it.
+        Object.defineProperty(response, "url", { value: request.url });
+        return response;
+    };
+}
+"#)]
+extern "C" {
+    fn stub_fetch(table: &str);
+}
+
+const STATES: &str = r#"{"time": 1700000000, "states": [
+    ["3c6444", "DLH9LF  ", "Germany", null, 1700000000, 1.4, 43.6, 10000.0, false, 230.0, 90.0, 0.0, null, 10500.0, "1000", false, 0]
+]}"#;
+
+const SEARCH: &str = r#"[{"lat": "43.6045", "lon": "1.4440", "display_name": "Toulouse, France"}]"#;
+
+fn json(body: &str) -> serde_json::Value {
+    serde_json::json!({
+        "status": 200,
+        "headers": {"content-type": "application/json"},
+        "body": body,
+    })
+}
+
+#[wasm_bindgen_test]
+async fn test_place_query_with_rate_limit_retry() {
+    stub_fetch(
+        &serde_json::json!({
+            "https://query.test/search": [json(SEARCH)],
+            "https://query.test/states/all": [
+                {"status": 429, "headers": {"x-rate-limit-retry-after-seconds": "0"}, "body": ""},
+                json(STATES),
+            ],
+        })
+        .to_string(),
+    );
+    let limiter =
+        RateLimiter::direct_with_clock(Quota::per_second(nonzero!(10u32)), &WebClock::default());
+    let client = OpenSkyClient::builder()
+        .base_url("https://query.test")
+        .rate_limiter(Arc::new(limiter))
+        .cache_ttl(Duration::from_secs(5))
+        .build()
+        .unwrap();
+    let geocoder = Geocoder::builder()
+        .base_url("https://query.test")
+        .build()
+        .unwrap();
+
+    let result = with_deadline(
+        Duration::from_secs(10),
+        run_query_with(
+            &client,
+            &geocoder,
+            &QueryInput::Place("Toulouse".into()),
+            SearchRadius::Degrees(1.0),
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result.location_name, "Toulouse, France");
+    assert_eq!(result.snapshot_time, Some(1700000000));
+    assert_eq!(result.aircraft.len(), 1);
+    assert_eq!(result.aircraft[0].icao24, "3c6444");
+}
+
+#[wasm_bindgen_test]
+async fn test_geocoder_waits_out_its_rate_limit() {
+    stub_fetch(
+        &serde_json::json!({
+            "https://pace.test/search": [json(SEARCH)],
+        })
+        .to_string(),
+    );
+    let geocoder = Geocoder::builder()
+        .base_url("https://pace.test")
+        .build()
+        .unwrap();
+
+    let started = Instant::now();
+    geocoder.geocode("Toulouse").await.unwrap().unwrap();
+    geocoder.geocode("Toulouse").await.unwrap().unwrap();
+
+    // Nominatim allows one request a second.
+    assert!(started.elapsed() >= Duration::from_millis(900));
+}
+
+#[wasm_bindgen_test]
+async fn test_deadline_expires() {
+    stub_fetch(
+        &serde_json::json!({
+            "https://stalled.test/states/all": [null],
+        })
+        .to_string(),
+    );
+    let client = OpenSkyClient::builder()
+        .base_url("https://stalled.test")
+        .build()
+        .unwrap();
+    let bbox = SearchRadius::Degrees(1.0).bbox_around(ifo::Coordinate::new(43.6, 1.4).unwrap());
+
+    let result = with_deadline(
+        Duration::from_millis(200),
+        client.get_aircraft_in_area(bbox),
+    )
+    .await;
+
+    assert!(matches!(result, Err(IfoError::Timeout { .. })));
+}